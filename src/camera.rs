@@ -1,3 +1,4 @@
+use crate::random::random_range;
 use crate::ray::*;
 use crate::vec3::*;
 
@@ -8,6 +9,11 @@ pub struct Camera {
     vertical: Vec3,
     lens_radius: f64,
     basis: [Vec3; 3],
+    viewport_height: f64,
+    /// Angle covered by one pixel, zero until the image height is known.
+    pixel_spread: f64,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
@@ -54,16 +60,41 @@ impl Camera {
             vertical,
             lens_radius,
             basis: [u, v, w],
+            viewport_height,
+            pixel_spread: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
+    /// Keeps the shutter open from time `open` to time `close`, giving rays
+    /// times spread evenly over that interval so that moving objects blur.
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Camera {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    /// Sets the height of the rendered image in pixels, so that rays get a
+    /// footprint as wide as a pixel.
+    pub fn with_image_height(mut self, height: usize) -> Camera {
+        self.pixel_spread = self.viewport_height / height.max(1) as f64;
+        self
+    }
+
     pub fn get_ray(&self, u: f64, v: f64) -> Ray {
         let random_point = self.lens_radius * Vec3::random_in_unit_disk();
         let offset = self.basis[0] * random_point.x() + self.basis[1] * random_point.y();
         let origin = self.origin + offset;
-        return Ray::new(
+        Ray::new(
             origin,
             (self.lower_left_corner + u * self.horizontal + v * self.vertical) - origin,
-        );
+        )
+        .with_footprint(0.0, self.pixel_spread)
+        .with_time(if self.shutter_close > self.shutter_open {
+            random_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        })
     }
 }
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser, ValueEnum};
+
+use rustracer::image::{ExrCompression, ExrOptions, ExrPixelType, ImageFormat};
+use rustracer::scene::RenderSettings;
+
+// Settings used by `--preview`: a quarter of the resolution and just enough
+// samples and bounces to judge composition and lighting.
+const PREVIEW_SCALE: usize = 4;
+const PREVIEW_SAMPLES_PER_PIXEL: u32 = 16;
+const PREVIEW_MAX_BOUNCES: i32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Binary PPM (P6)
+    Ppm,
+    /// 8-bit RGB PNG
+    Png,
+    /// Linear Radiance RGBE
+    Hdr,
+    /// Linear OpenEXR
+    Exr,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExrPixelTypeArg {
+    /// 16-bit floating point
+    Half,
+    /// 32-bit floating point
+    Float,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExrCompressionArg {
+    None,
+    Rle,
+    Zip,
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "rustracer", version, about = "Simple path tracer")]
+pub struct Cli {
+    /// Scene description file. The built-in random spheres scene is
+    /// rendered when omitted.
+    pub scene: Option<PathBuf>,
+
+    /// Where to write the image.
+    #[arg(short, long, value_name = "PATH", default_value = "image.png")]
+    pub output: PathBuf,
+
+    /// Format of the written image. Guessed from the extension of the
+    /// output path when omitted.
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Sample type of OpenEXR output.
+    #[arg(long, value_enum, value_name = "TYPE")]
+    pub exr_pixel_type: Option<ExrPixelTypeArg>,
+
+    /// Compression of OpenEXR output.
+    #[arg(long, value_enum, value_name = "COMPRESSION")]
+    pub exr_compression: Option<ExrCompressionArg>,
+
+    /// Image width in pixels. When given without --height, the height
+    /// follows the aspect ratio of the scene.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: Option<u32>,
+
+    /// Image height in pixels. When given without --width, the width
+    /// follows the aspect ratio of the scene.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: Option<u32>,
+
+    /// Number of samples traced for every pixel.
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub samples: Option<u32>,
+
+    /// Maximum number of bounces of every path.
+    #[arg(short = 'b', long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub max_bounces: Option<i32>,
+
+    /// Number of rendering threads. Defaults to one per CPU.
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threads: Option<u32>,
+
+    /// Seed for the random number generator, making renders reproducible.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Fast low quality render: reduced resolution, samples and bounces.
+    #[arg(long, conflicts_with_all = ["width", "height", "samples", "max_bounces"])]
+    pub preview: bool,
+}
+
+impl Cli {
+    /// Parses the command line, exiting with a usage error if it is invalid.
+    pub fn parse_and_validate() -> Cli {
+        let cli = Cli::parse();
+        if let Err(message) = cli.validate() {
+            Cli::command()
+                .error(clap::error::ErrorKind::ValueValidation, message)
+                .exit();
+        }
+        cli
+    }
+
+    /// Checks the combinations of arguments that clap cannot express.
+    fn validate(&self) -> Result<(), String> {
+        if self.format.is_none() && ImageFormat::from_path(&self.output).is_none() {
+            return Err(format!(
+                "cannot guess the image format of '{}', use --format",
+                self.output.display()
+            ));
+        }
+        let exr_options = self.exr_pixel_type.is_some() || self.exr_compression.is_some();
+        if exr_options && !matches!(self.image_format(), ImageFormat::Exr(_)) {
+            return Err("--exr-pixel-type and --exr-compression need OpenEXR output".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn image_format(&self) -> ImageFormat {
+        let format = match self.format {
+            Some(OutputFormat::Ppm) => ImageFormat::Ppm,
+            Some(OutputFormat::Png) => ImageFormat::Png,
+            Some(OutputFormat::Hdr) => ImageFormat::Hdr,
+            Some(OutputFormat::Exr) => ImageFormat::Exr(ExrOptions::default()),
+            None => ImageFormat::from_path(&self.output).unwrap_or(ImageFormat::Png),
+        };
+
+        match format {
+            ImageFormat::Exr(mut options) => {
+                match self.exr_pixel_type {
+                    Some(ExrPixelTypeArg::Half) => options.pixel_type = ExrPixelType::Half,
+                    Some(ExrPixelTypeArg::Float) => options.pixel_type = ExrPixelType::Float,
+                    None => {}
+                }
+                match self.exr_compression {
+                    Some(ExrCompressionArg::None) => options.compression = ExrCompression::None,
+                    Some(ExrCompressionArg::Rle) => options.compression = ExrCompression::Rle,
+                    Some(ExrCompressionArg::Zip) => options.compression = ExrCompression::Zip,
+                    None => {}
+                }
+                ImageFormat::Exr(options)
+            }
+            format => format,
+        }
+    }
+
+    /// Applies the command line overrides on top of the scene settings.
+    pub fn apply(&self, settings: &mut RenderSettings) {
+        let aspect_ratio = settings.aspect_ratio();
+
+        if self.preview {
+            settings.width = (settings.width / PREVIEW_SCALE).max(1);
+            settings.height = (settings.height / PREVIEW_SCALE).max(1);
+            settings.samples_per_pixel = PREVIEW_SAMPLES_PER_PIXEL;
+            settings.max_bounces = PREVIEW_MAX_BOUNCES;
+        }
+
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => {
+                settings.width = width as usize;
+                settings.height = height as usize;
+            }
+            (Some(width), None) => {
+                settings.width = width as usize;
+                settings.height = ((width as f64 / aspect_ratio) as usize).max(1);
+            }
+            (None, Some(height)) => {
+                settings.width = ((height as f64 * aspect_ratio) as usize).max(1);
+                settings.height = height as usize;
+            }
+            (None, None) => {}
+        }
+
+        if let Some(samples) = self.samples {
+            settings.samples_per_pixel = samples;
+        }
+        if let Some(max_bounces) = self.max_bounces {
+            settings.max_bounces = max_bounces;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> RenderSettings {
+        RenderSettings {
+            width: 1600,
+            height: 900,
+            samples_per_pixel: 500,
+            max_bounces: 50,
+        }
+    }
+
+    fn apply(args: &[&str]) -> RenderSettings {
+        let cli = Cli::try_parse_from(args).unwrap();
+        let mut settings = settings();
+        cli.apply(&mut settings);
+        settings
+    }
+
+    #[test]
+    fn overrides_settings() {
+        let settings = apply(&["rustracer", "scene.toml", "--width", "800", "-s", "10"]);
+        assert_eq!((settings.width, settings.height), (800, 450));
+        assert_eq!(settings.samples_per_pixel, 10);
+        assert_eq!(settings.max_bounces, 50);
+
+        let settings = apply(&["rustracer", "--height", "90", "--width", "100"]);
+        assert_eq!((settings.width, settings.height), (100, 90));
+    }
+
+    #[test]
+    fn preview_preset() {
+        let settings = apply(&["rustracer", "--preview"]);
+        assert_eq!((settings.width, settings.height), (400, 225));
+        assert_eq!(settings.samples_per_pixel, PREVIEW_SAMPLES_PER_PIXEL);
+        assert_eq!(settings.max_bounces, PREVIEW_MAX_BOUNCES);
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(Cli::try_parse_from(["rustracer", "--preview", "--samples", "4"]).is_err());
+        assert!(Cli::try_parse_from(["rustracer", "--width", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rustracer", "--threads", "0"]).is_err());
+        assert!(Cli::try_parse_from(["rustracer", "--samples", "many"]).is_err());
+        assert!(Cli::try_parse_from(["rustracer", "--format", "jpeg"]).is_err());
+
+        let cli = Cli::try_parse_from(["rustracer", "-o", "image.tga"]).unwrap();
+        assert!(cli.validate().is_err());
+
+        let cli = Cli::try_parse_from(["rustracer", "--exr-compression", "rle"]).unwrap();
+        assert!(cli.validate().is_err());
+    }
+
+    #[test]
+    fn image_format() {
+        let cli = Cli::try_parse_from(["rustracer", "-o", "image.PPM"]).unwrap();
+        assert_eq!(cli.image_format(), ImageFormat::Ppm);
+        let cli = Cli::try_parse_from(["rustracer", "-o", "image.ppm", "-f", "png"]).unwrap();
+        assert_eq!(cli.image_format(), ImageFormat::Png);
+        let cli = Cli::try_parse_from(["rustracer"]).unwrap();
+        assert_eq!(cli.image_format(), ImageFormat::Png);
+
+        let cli =
+            Cli::try_parse_from(["rustracer", "-o", "a.exr", "--exr-pixel-type", "float"]).unwrap();
+        assert!(cli.validate().is_ok());
+        assert_eq!(
+            cli.image_format(),
+            ImageFormat::Exr(ExrOptions {
+                pixel_type: ExrPixelType::Float,
+                compression: ExrCompression::Zip,
+            })
+        );
+    }
+}
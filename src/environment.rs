@@ -0,0 +1,223 @@
+use std::f64::consts::PI;
+
+use crate::image::Image;
+use crate::random::random_f64;
+use crate::sampling::Distribution2D;
+use crate::vec3::*;
+
+/// A direction towards the environment chosen by `Environment::sample`.
+pub struct EnvironmentSample {
+    pub direction: Vec3,
+    pub radiance: Color,
+    /// Density of the direction, with respect to solid angle.
+    pub pdf: f64,
+}
+
+/// Light arriving from infinitely far away, seen by rays that leave the
+/// scene.
+pub trait Environment: Sync + Send {
+    /// Radiance arriving from `direction`, which need not be normalized.
+    fn radiance(&self, direction: &Vec3) -> Color;
+
+    /// Picks a direction with a density roughly proportional to the radiance
+    /// arriving from it, or `None` for environments that are not worth
+    /// sampling directly (their light is then only found by chance).
+    fn sample(&self) -> Option<EnvironmentSample> {
+        None
+    }
+
+    /// The density with which `sample` picks `direction`.
+    fn pdf(&self, _direction: &Vec3) -> f64 {
+        0.0
+    }
+}
+
+/// The same colour in every direction; black makes a scene lit only by its
+/// emissive objects.
+pub struct ConstantEnvironment {
+    pub color: Color,
+}
+
+impl Environment for ConstantEnvironment {
+    fn radiance(&self, _direction: &Vec3) -> Color {
+        self.color
+    }
+}
+
+/// Blends linearly from `bottom` (straight down) to `top` (straight up).
+pub struct GradientEnvironment {
+    pub bottom: Color,
+    pub top: Color,
+}
+
+impl Default for GradientEnvironment {
+    /// The white to light blue sky of "Ray Tracing in One Weekend".
+    fn default() -> GradientEnvironment {
+        GradientEnvironment {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let t = 0.5 * (direction.normalize().y() + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+/// An equirectangular (latitude-longitude) image wrapped around the scene.
+/// The top row of the image is straight up and its centre column looks down
+/// the -z axis before rotation.
+pub struct ImageEnvironment {
+    image: Image,
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+fn luminance(color: &Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+impl ImageEnvironment {
+    /// `rotation` turns the image around the vertical axis, in degrees, and
+    /// `intensity` scales its radiance.
+    pub fn new(image: Image, rotation: f64, intensity: f64) -> ImageEnvironment {
+        // Rows near the poles cover less of the sphere than those near the
+        // horizon, hence the sin(theta) factor.
+        let function: Vec<f64> = image
+            .pixels
+            .chunks(image.width)
+            .enumerate()
+            .flat_map(|(y, row)| {
+                let sin_theta = (PI * (y as f64 + 0.5) / image.height as f64).sin();
+                row.iter().map(move |pixel| luminance(pixel) * sin_theta)
+            })
+            .collect();
+        let distribution = Distribution2D::new(&function, image.width, image.height);
+
+        ImageEnvironment {
+            image,
+            rotation: rotation.to_radians(),
+            intensity,
+            distribution,
+        }
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Texture coordinates in `[0, 1)` of the image point seen in
+    /// `direction`, `u` growing to the right and `v` downwards.
+    fn uv(&self, direction: &Vec3) -> (f64, f64) {
+        let direction = direction.normalize();
+        let phi = direction.x().atan2(-direction.z()) - self.rotation;
+        let theta = direction.y().clamp(-1.0, 1.0).acos();
+        let u = (0.5 + phi / (2.0 * PI)).rem_euclid(1.0);
+        (u, theta / PI)
+    }
+
+    /// The inverse of `uv`, also returning sin(theta).
+    fn direction(&self, u: f64, v: f64) -> (Vec3, f64) {
+        let phi = (u - 0.5) * 2.0 * PI + self.rotation;
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        let direction = Vec3::new(sin_theta * phi.sin(), cos_theta, -sin_theta * phi.cos());
+        (direction, sin_theta)
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let (u, v) = self.uv(direction);
+        let x = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let y = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        self.intensity * self.image.pixels[y * self.image.width + x]
+    }
+
+    fn sample(&self) -> Option<EnvironmentSample> {
+        if self.distribution.integral() <= 0.0 {
+            return None;
+        }
+        let ((u, v), pdf) = self.distribution.sample(random_f64(), random_f64());
+        let (direction, sin_theta) = self.direction(u, v);
+        if pdf <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        // The image maps to the sphere through phi = 2 pi u and theta = pi v.
+        Some(EnvironmentSample {
+            direction,
+            radiance: self.radiance(&direction),
+            pdf: pdf / (2.0 * PI * PI * sin_theta),
+        })
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        let (u, v) = self.uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_directions_to_pixels() {
+        // Four columns (+z, -x, -z and +x when unrotated) on two rows.
+        let pixels = (0..8).map(|i| Color::new(i as f64, 0.0, 0.0)).collect();
+        let environment = ImageEnvironment::new(Image::new(4, 2, pixels), 0.0, 2.0);
+        let red = |x, y, z| environment.radiance(&Vec3::new(x, y, z)).x();
+
+        assert_eq!(red(0.0, 0.1, -1.0), 4.0);
+        assert_eq!(red(1.0, 0.1, 0.0), 6.0);
+        assert_eq!(red(0.0, 0.1, 1.0), 0.0);
+        assert_eq!(red(-1.0, 0.1, 0.0), 2.0);
+        assert_eq!(red(0.0, -0.1, -1.0), 12.0);
+
+        let rotated = ImageEnvironment::new(environment.image, 90.0, 1.0);
+        assert_eq!(rotated.radiance(&Vec3::new(1.0, 0.1, 0.0)).x(), 2.0);
+    }
+
+    #[test]
+    fn samples_bright_pixels() {
+        let mut pixels = vec![Color::new(0.01, 0.01, 0.01); 32 * 16];
+        pixels[5 * 32 + 20] = Color::new(1000.0, 1000.0, 1000.0);
+        let environment = ImageEnvironment::new(Image::new(32, 16, pixels), 30.0, 1.0);
+
+        let mut bright = 0;
+        for _ in 0..1000 {
+            let sample = environment.sample().unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            let pdf = environment.pdf(&sample.direction);
+            assert!((sample.pdf - pdf).abs() <= 1e-9 * pdf);
+            if sample.radiance.x() > 1.0 {
+                bright += 1;
+            }
+        }
+        assert!(bright > 900, "{}", bright);
+
+        // The density integrates to one over the sphere.
+        let n = 200;
+        let mut integral = 0.0;
+        for i in 0..n {
+            for j in 0..2 * n {
+                let theta = PI * (i as f64 + 0.5) / n as f64;
+                let phi = PI * (j as f64 + 0.5) / n as f64;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                integral += environment.pdf(&direction) * theta.sin() * (PI / n as f64).powi(2);
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.01, "{}", integral);
+    }
+}
@@ -0,0 +1,732 @@
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use super::Image;
+use crate::vec3::Color;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const VERSION: u32 = 2;
+// Deflate cannot shrink data more than about 1032 times, and RLE less, so
+// an image cannot hold more pixel data than this many times its size.
+const MAX_COMPRESSION_RATIO: usize = 1032;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExrPixelType {
+    Half,
+    Float,
+}
+
+impl ExrPixelType {
+    fn id(&self) -> i32 {
+        match self {
+            ExrPixelType::Half => 1,
+            ExrPixelType::Float => 2,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ExrPixelType::Half => 2,
+            ExrPixelType::Float => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExrCompression {
+    None,
+    Rle,
+    Zip,
+}
+
+impl ExrCompression {
+    fn id(&self) -> u8 {
+        match self {
+            ExrCompression::None => 0,
+            ExrCompression::Rle => 1,
+            ExrCompression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(&self) -> usize {
+        match self {
+            ExrCompression::None | ExrCompression::Rle => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExrOptions {
+    pub pixel_type: ExrPixelType,
+    pub compression: ExrCompression,
+}
+
+impl Default for ExrOptions {
+    fn default() -> ExrOptions {
+        ExrOptions {
+            pixel_type: ExrPixelType::Half,
+            compression: ExrCompression::Zip,
+        }
+    }
+}
+
+/// Converts to IEEE 754 half precision, rounding to nearest even. Values
+/// too large for a half become infinities.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN (keeping NaNs quiet and non-zero).
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    if half_exponent <= 0 {
+        // Subnormal half (or zero).
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half_mantissa = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round = remainder > halfway || (remainder == halfway && half_mantissa & 1 == 1);
+        return sign | (half_mantissa + round as u32) as u16;
+    }
+
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    let round = remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1);
+    // A carry out of the mantissa correctly bumps the exponent (possibly up
+    // to infinity).
+    sign | (half + round as u32) as u16
+}
+
+/// Converts from IEEE 754 half precision, exactly.
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn box2i(width: usize, height: usize) -> Vec<u8> {
+    [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+fn header(width: usize, height: usize, options: &ExrOptions) -> Vec<u8> {
+    let mut channels = vec![];
+    // Channels must be listed in alphabetical order.
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&options.pixel_type.id().to_le_bytes());
+        // pLinear and three reserved bytes.
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+
+    let mut header = vec![];
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[options.compression.id()],
+    );
+    attribute(&mut header, "dataWindow", "box2i", &box2i(width, height));
+    attribute(&mut header, "displayWindow", "box2i", &box2i(width, height));
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    let center: Vec<u8> = [0f32, 0f32].iter().flat_map(|v| v.to_le_bytes()).collect();
+    attribute(&mut header, "screenWindowCenter", "v2f", &center);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+    header
+}
+
+/// Reorders the bytes and applies the delta predictor shared by the RLE and
+/// ZIP compression schemes, which makes the data much more compressible.
+fn predict(data: &[u8]) -> Vec<u8> {
+    let half = data.len().div_ceil(2);
+    let mut reordered = vec![0; data.len()];
+    for (i, &byte) in data.iter().enumerate() {
+        if i % 2 == 0 {
+            reordered[i / 2] = byte;
+        } else {
+            reordered[half + i / 2] = byte;
+        }
+    }
+
+    let mut previous = reordered.first().copied().unwrap_or(0);
+    for byte in reordered.iter_mut().skip(1) {
+        let current = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+    reordered
+}
+
+/// The run-length encoding of the OpenEXR library: a non-negative count `n`
+/// is followed by a byte repeated `n + 1` times, a negative count `-n` by `n`
+/// literal bytes.
+fn rle_compress(data: &[u8]) -> Vec<u8> {
+    const MIN_RUN_LENGTH: usize = 3;
+    const MAX_RUN_LENGTH: usize = 127;
+
+    let mut out = vec![];
+    let mut run_start = 0;
+    let mut run_end = 1;
+    while run_start < data.len() {
+        while run_end < data.len()
+            && data[run_start] == data[run_end]
+            && run_end - run_start - 1 < MAX_RUN_LENGTH
+        {
+            run_end += 1;
+        }
+
+        if run_end - run_start >= MIN_RUN_LENGTH {
+            out.push((run_end - run_start - 1) as u8);
+            out.push(data[run_start]);
+            run_start = run_end;
+        } else {
+            while run_end < data.len()
+                && ((run_end + 1 >= data.len() || data[run_end] != data[run_end + 1])
+                    || (run_end + 2 >= data.len() || data[run_end + 1] != data[run_end + 2]))
+                && run_end - run_start < MAX_RUN_LENGTH
+            {
+                run_end += 1;
+            }
+            out.push((-((run_end - run_start) as i32)) as u8);
+            out.extend_from_slice(&data[run_start..run_end]);
+            run_start = run_end;
+        }
+        run_end += 1;
+    }
+    out
+}
+
+/// Undoes `predict`.
+fn unpredict(data: &mut [u8]) -> Vec<u8> {
+    for i in 1..data.len() {
+        data[i] = data[i - 1].wrapping_add(data[i]).wrapping_sub(128);
+    }
+    let half = data.len().div_ceil(2);
+    (0..data.len())
+        .map(|i| {
+            if i % 2 == 0 {
+                data[i / 2]
+            } else {
+                data[half + i / 2]
+            }
+        })
+        .collect()
+}
+
+/// Undoes `rle_compress`.
+fn rle_decompress(data: &[u8], expected_size: usize) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(expected_size);
+    let mut i = 0;
+    while i < data.len() {
+        let count = data[i] as i8;
+        i += 1;
+        if count < 0 {
+            let literal = data
+                .get(i..i + (-(count as i32)) as usize)
+                .ok_or_else(|| invalid_data("truncated OpenEXR run"))?;
+            out.extend_from_slice(literal);
+            i += literal.len();
+        } else {
+            let value = *data
+                .get(i)
+                .ok_or_else(|| invalid_data("truncated OpenEXR run"))?;
+            out.resize(out.len() + count as usize + 1, value);
+            i += 1;
+        }
+        if out.len() > expected_size {
+            return Err(invalid_data("OpenEXR run overflows its block"));
+        }
+    }
+    Ok(out)
+}
+
+fn compress(data: Vec<u8>, compression: ExrCompression) -> io::Result<Vec<u8>> {
+    let compressed = match compression {
+        ExrCompression::None => return Ok(data),
+        ExrCompression::Rle => rle_compress(&predict(&data)),
+        ExrCompression::Zip => {
+            let mut encoder = ZlibEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(&predict(&data))?;
+            encoder.finish()?
+        }
+    };
+    // Blocks that do not shrink are stored uncompressed; readers detect them
+    // by their size.
+    Ok(if compressed.len() < data.len() {
+        compressed
+    } else {
+        data
+    })
+}
+
+/// Writes `image` as a single-part scanline OpenEXR file with linear R, G and
+/// B channels.
+pub fn write_exr(out: &mut dyn Write, image: &Image, options: &ExrOptions) -> io::Result<()> {
+    let lines_per_block = options.compression.lines_per_block();
+    let sample_size = options.pixel_type.size();
+
+    let mut blocks = vec![];
+    for (block_index, rows) in image
+        .pixels
+        .chunks(image.width * lines_per_block)
+        .enumerate()
+    {
+        let mut data = Vec::with_capacity(rows.len() * 3 * sample_size);
+        for row in rows.chunks(image.width) {
+            for channel in [2, 1, 0].iter() {
+                for pixel in row.iter() {
+                    let value = pixel[*channel] as f32;
+                    match options.pixel_type {
+                        ExrPixelType::Half => {
+                            data.extend_from_slice(&f32_to_f16(value).to_le_bytes())
+                        }
+                        ExrPixelType::Float => data.extend_from_slice(&value.to_le_bytes()),
+                    }
+                }
+            }
+        }
+        let y = (block_index * lines_per_block) as i32;
+        blocks.push((y, compress(data, options.compression)?));
+    }
+
+    let header = header(image.width, image.height, options);
+    out.write_all(&MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&header)?;
+
+    // Offset table: the absolute position of every block in the file.
+    let mut offset = (MAGIC.len() + 4 + header.len() + 8 * blocks.len()) as u64;
+    for (_, data) in blocks.iter() {
+        out.write_all(&offset.to_le_bytes())?;
+        offset += 8 + data.len() as u64;
+    }
+
+    for (y, data) in blocks.iter() {
+        out.write_all(&y.to_le_bytes())?;
+        out.write_all(&(data.len() as i32).to_le_bytes())?;
+        out.write_all(data)?;
+    }
+    Ok(())
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Reads the little-endian values of an OpenEXR file.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .position
+            .checked_add(count)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| invalid_data("truncated OpenEXR file"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<&'a str> {
+        let rest = &self.data[self.position.min(self.data.len())..];
+        let end = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid_data("truncated OpenEXR file"))?;
+        self.position += end + 1;
+        std::str::from_utf8(&rest[..end]).map_err(|_| invalid_data("invalid OpenEXR header"))
+    }
+}
+
+struct Channel {
+    name: String,
+    pixel_type: i32,
+}
+
+impl Channel {
+    fn size(&self) -> usize {
+        if self.pixel_type == 1 {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn value(&self, bytes: &[u8]) -> f64 {
+        match self.pixel_type {
+            0 => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            1 => f16_to_f32(u16::from_le_bytes(bytes.try_into().unwrap())) as f64,
+            _ => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        }
+    }
+}
+
+fn parse_channels(value: &[u8]) -> io::Result<Vec<Channel>> {
+    let mut reader = Reader {
+        data: value,
+        position: 0,
+    };
+    let mut channels = vec![];
+    loop {
+        let name = reader.string()?;
+        if name.is_empty() {
+            return Ok(channels);
+        }
+        let pixel_type = reader.i32()?;
+        reader.bytes(4)?;
+        let (x_sampling, y_sampling) = (reader.i32()?, reader.i32()?);
+        if !(0..=2).contains(&pixel_type) {
+            return Err(invalid_data("invalid OpenEXR pixel type"));
+        }
+        if (x_sampling, y_sampling) != (1, 1) {
+            return Err(invalid_data(
+                "subsampled OpenEXR channels are not supported",
+            ));
+        }
+        channels.push(Channel {
+            name: name.to_string(),
+            pixel_type,
+        });
+    }
+}
+
+fn decompress(data: &[u8], compression: u8, expected_size: usize) -> io::Result<Vec<u8>> {
+    // Blocks that would not shrink are stored uncompressed.
+    if data.len() == expected_size {
+        return Ok(data.to_vec());
+    }
+    let mut predicted = match compression {
+        0 => return Err(invalid_data("OpenEXR block has the wrong size")),
+        1 => rle_decompress(data, expected_size)?,
+        _ => {
+            let mut out = Vec::with_capacity(expected_size);
+            ZlibDecoder::new(data)
+                .take(expected_size as u64 + 1)
+                .read_to_end(&mut out)?;
+            out
+        }
+    };
+    if predicted.len() != expected_size {
+        return Err(invalid_data("OpenEXR block has the wrong size"));
+    }
+    Ok(unpredict(&mut predicted))
+}
+
+/// Reads a single-part scanline OpenEXR file without compression or with
+/// RLE, ZIPS or ZIP compression. The R, G and B channels are used, or Y for
+/// greyscale images.
+pub fn read_exr(data: &[u8]) -> io::Result<Image> {
+    let mut reader = Reader { data, position: 0 };
+    if reader.bytes(4)? != MAGIC {
+        return Err(invalid_data("not an OpenEXR file"));
+    }
+    let version = reader.i32()?;
+    if version & 0xff != VERSION as i32 {
+        return Err(invalid_data("unsupported OpenEXR version"));
+    }
+    if version & 0x1a00 != 0 {
+        return Err(invalid_data(
+            "tiled, deep and multi-part OpenEXR files are not supported",
+        ));
+    }
+
+    let mut channels = None;
+    let mut compression = None;
+    let mut data_window = None;
+    loop {
+        let name = reader.string()?;
+        if name.is_empty() {
+            break;
+        }
+        reader.string()?;
+        let size = reader.i32()?;
+        let value = reader.bytes(size.max(0) as usize)?;
+        match name {
+            "channels" => channels = Some(parse_channels(value)?),
+            "compression" => compression = value.first().copied(),
+            "dataWindow" if value.len() == 16 => {
+                let mut window = Reader {
+                    data: value,
+                    position: 0,
+                };
+                data_window = Some([window.i32()?, window.i32()?, window.i32()?, window.i32()?]);
+            }
+            _ => {}
+        }
+    }
+
+    let (channels, compression, [x_min, y_min, x_max, y_max]) =
+        match (channels, compression, data_window) {
+            (Some(channels), Some(compression), Some(window)) => (channels, compression, window),
+            _ => return Err(invalid_data("incomplete OpenEXR header")),
+        };
+    let lines_per_block = match compression {
+        0..=2 => 1,
+        3 => 16,
+        _ => {
+            return Err(invalid_data(format!(
+                "unsupported OpenEXR compression {}",
+                compression
+            )))
+        }
+    };
+    if x_max < x_min || y_max < y_min {
+        return Err(invalid_data("empty OpenEXR data window"));
+    }
+    let width = (x_max as i64 - x_min as i64 + 1) as usize;
+    let height = (y_max as i64 - y_min as i64 + 1) as usize;
+
+    let find = |name: &str| channels.iter().position(|channel| channel.name == name);
+    let rgb = match (find("R"), find("G"), find("B"), find("Y")) {
+        (Some(r), Some(g), Some(b), _) => [r, g, b],
+        (_, _, _, Some(y)) => [y, y, y],
+        _ => return Err(invalid_data("OpenEXR file has no RGB or Y channels")),
+    };
+    let line_size = channels.iter().try_fold(0usize, |sum, channel| {
+        width.checked_mul(channel.size())?.checked_add(sum)
+    });
+    let line_size = match line_size {
+        Some(line_size)
+            if width.checked_mul(height).is_some()
+                && line_size.checked_mul(height).is_some_and(|size| {
+                    size <= data.len().saturating_mul(MAX_COMPRESSION_RATIO)
+                }) =>
+        {
+            line_size
+        }
+        _ => {
+            return Err(invalid_data(
+                "OpenEXR data window is too large for the file",
+            ))
+        }
+    };
+
+    let block_count = height.div_ceil(lines_per_block);
+    let offsets = (0..block_count)
+        .map(|_| reader.u64())
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut pixels = vec![Color::new(0.0, 0.0, 0.0); width * height];
+    for offset in offsets {
+        reader.position = offset as usize;
+        let y = reader.i32()? as i64 - y_min as i64;
+        let size = reader.i32()?;
+        let block = reader.bytes(size.max(0) as usize)?;
+        if y < 0 || y as usize >= height {
+            return Err(invalid_data("OpenEXR block outside of the data window"));
+        }
+        let y = y as usize;
+        let lines = lines_per_block.min(height - y);
+        let block = decompress(block, compression, lines * line_size)?;
+
+        for (line, data) in block.chunks(line_size).enumerate() {
+            let row = &mut pixels[(y + line) * width..(y + line + 1) * width];
+            let mut start = 0;
+            for (index, channel) in channels.iter().enumerate() {
+                let size = channel.size();
+                let samples = &data[start..start + width * size];
+                start += width * size;
+                for (component, _) in rgb.iter().enumerate().filter(|(_, &c)| c == index) {
+                    for (pixel, bytes) in row.iter_mut().zip(samples.chunks(size)) {
+                        pixel[component] = channel.value(bytes);
+                    }
+                }
+            }
+        }
+    }
+    Ok(Image::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_half() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+        // Smallest subnormal half.
+        assert_eq!(f32_to_f16(5.960_464_5e-8), 0x0001);
+        // Rounds to nearest.
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0 + 1.0 / 4096.0), 0x3c01);
+    }
+
+    #[test]
+    fn converts_from_half() {
+        for &value in [0.0, 1.0, -2.0, 0.5, 65504.0, 5.960_464_5e-8, 0.333].iter() {
+            let half = f32_to_f16(value);
+            assert_eq!(f32_to_f16(f16_to_f32(half)), half);
+        }
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+    }
+
+    #[test]
+    fn round_trips_through_reader() {
+        let pixels = (0..7 * 20)
+            .map(|i| Color::new(i as f64, 0.5, if i % 3 == 0 { 0.0 } else { 0.25 }))
+            .collect();
+        let image = Image::new(7, 20, pixels);
+        for &pixel_type in [ExrPixelType::Half, ExrPixelType::Float].iter() {
+            for &compression in [
+                ExrCompression::None,
+                ExrCompression::Rle,
+                ExrCompression::Zip,
+            ]
+            .iter()
+            {
+                let options = ExrOptions {
+                    pixel_type,
+                    compression,
+                };
+                let mut out = vec![];
+                write_exr(&mut out, &image, &options).unwrap();
+                let read = read_exr(&out).unwrap();
+                assert_eq!((read.width, read.height), (7, 20));
+                assert!(read.pixels == image.pixels, "{:?}", options);
+            }
+        }
+    }
+
+    #[test]
+    fn run_length_encoding() {
+        assert_eq!(rle_compress(&[5, 5, 5, 5]), vec![3, 5]);
+        assert_eq!(rle_compress(&[1, 2, 3]), vec![253, 1, 2, 3]);
+        assert_eq!(rle_compress(&[1, 2, 7, 7, 7]), vec![254, 1, 2, 2, 7]);
+
+        let data = [3, 3, 3, 3, 9, 1, 200, 200, 200, 200, 200];
+        let compressed = rle_compress(&data);
+        assert_eq!(rle_decompress(&compressed, data.len()).unwrap(), data);
+        assert_eq!(unpredict(&mut predict(&data)), data);
+    }
+
+    #[test]
+    fn writes_offset_table() {
+        let image = Image::new(4, 3, vec![crate::vec3::Color::new(1.0, 0.5, 0.25); 12]);
+        let options = ExrOptions {
+            pixel_type: ExrPixelType::Float,
+            compression: ExrCompression::None,
+        };
+        let mut out = vec![];
+        write_exr(&mut out, &image, &options).unwrap();
+        assert_eq!(&out[..4], &MAGIC);
+
+        let header_end = 8 + header(4, 3, &options).len();
+        let block_size = 8 + 4 * 3 * 4;
+        assert_eq!(out.len(), header_end + 3 * 8 + 3 * block_size);
+        let first_offset = u64::from_le_bytes(out[header_end..header_end + 8].try_into().unwrap());
+        assert_eq!(first_offset as usize, header_end + 3 * 8);
+    }
+
+    #[test]
+    fn rejects_truncated_blocks() {
+        let image = Image::new(4, 3, vec![Color::new(1.0, 0.5, 0.25); 12]);
+        let options = ExrOptions {
+            pixel_type: ExrPixelType::Float,
+            compression: ExrCompression::None,
+        };
+        let mut out = vec![];
+        write_exr(&mut out, &image, &options).unwrap();
+        // Drops the last sample of the last scanline, and says so in the
+        // size of its block.
+        out.truncate(out.len() - 4);
+        let size_field = out.len() - (4 * 3 * 4 - 4) - 4;
+        out[size_field..size_field + 4].copy_from_slice(&(4 * 3 * 4 - 4i32).to_le_bytes());
+        let error = read_exr(&out).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_oversized_data_windows() {
+        let image = Image::new(4, 3, vec![Color::new(1.0, 0.5, 0.25); 12]);
+        let mut out = vec![];
+        write_exr(&mut out, &image, &ExrOptions::default()).unwrap();
+        // Stretches the data window to the largest one the header can give.
+        let attribute = b"dataWindow\0box2i\0";
+        let start = out
+            .windows(attribute.len())
+            .position(|window| window == attribute)
+            .unwrap()
+            + attribute.len()
+            + 4;
+        for (index, value) in [0, 0, i32::MAX, i32::MAX].iter().enumerate() {
+            let field = start + 4 * index;
+            out[field..field + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        let error = read_exr(&out).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "OpenEXR data window is too large for the file"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::io::{self, Write};
+use std::str;
+
+use super::Image;
+use crate::vec3::Color;
+
+// Scanlines of this width range are run-length encoded, anything else is
+// written flat as the format requires.
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+
+/// Encodes a linear colour with the shared-exponent RGBE representation.
+pub fn to_rgbe(color: &Color) -> [u8; 4] {
+    let v = color.x().max(color.y()).max(color.z());
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // Split v into mantissa and exponent, v = m * 2^e with m in [0.5, 1).
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1.0 {
+        e += 1;
+    }
+    let scale = 256.0 / 2f64.powi(e);
+    let encode = |c: f64| (c.max(0.0) * scale).min(255.0) as u8;
+    [
+        encode(color.x()),
+        encode(color.y()),
+        encode(color.z()),
+        (e + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Decodes a shared-exponent RGBE pixel into a linear colour.
+pub fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let scale = 2f64.powi(rgbe[3] as i32 - 136);
+    Color::new(
+        rgbe[0] as f64 * scale,
+        rgbe[1] as f64 * scale,
+        rgbe[2] as f64 * scale,
+    )
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "truncated Radiance HDR data")
+}
+
+fn write_rle_channel(out: &mut dyn Write, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    const MAX_COUNT: usize = 127;
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < MAX_COUNT && data[i + run] == data[i] {
+            run += 1;
+        }
+        if run >= MIN_RUN {
+            for chunk in data[literal_start..i].chunks(MAX_COUNT) {
+                out.write_all(&[chunk.len() as u8])?;
+                out.write_all(chunk)?;
+            }
+            out.write_all(&[128 + run as u8, data[i]])?;
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    for chunk in data[literal_start..].chunks(MAX_COUNT) {
+        out.write_all(&[chunk.len() as u8])?;
+        out.write_all(chunk)?;
+    }
+    Ok(())
+}
+
+/// Writes `image` as a Radiance RGBE (.hdr) file, keeping the linear
+/// radiance values.
+pub fn write_hdr(out: &mut dyn Write, image: &Image) -> io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\n# Written by rustracer\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        image.height, image.width
+    )?;
+
+    let width = image.width;
+    for row in image.pixels.chunks(width) {
+        let rgbe: Vec<[u8; 4]> = row.iter().map(to_rgbe).collect();
+        if !(MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width) {
+            for pixel in rgbe.iter() {
+                out.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+        for channel in 0..4 {
+            let data: Vec<u8> = rgbe.iter().map(|pixel| pixel[channel]).collect();
+            write_rle_channel(out, &data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one text line of the header, without its line feed.
+fn read_line<'a>(data: &'a [u8], position: &mut usize) -> io::Result<&'a str> {
+    let rest = &data[*position..];
+    let end = rest
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(truncated)?;
+    *position += end + 1;
+    str::from_utf8(&rest[..end]).map_err(|_| invalid_data("invalid Radiance HDR header"))
+}
+
+fn read_rle_scanline(
+    data: &[u8],
+    position: &mut usize,
+    scanline: &mut [[u8; 4]],
+) -> io::Result<()> {
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < scanline.len() {
+            let count = *data.get(*position).ok_or_else(truncated)? as usize;
+            *position += 1;
+            if count > 128 {
+                let count = count - 128;
+                let value = *data.get(*position).ok_or_else(truncated)?;
+                *position += 1;
+                if x + count > scanline.len() {
+                    return Err(invalid_data("run overflows Radiance HDR scanline"));
+                }
+                for pixel in scanline[x..x + count].iter_mut() {
+                    pixel[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > scanline.len() {
+                    return Err(invalid_data("invalid Radiance HDR run length"));
+                }
+                let values = data
+                    .get(*position..*position + count)
+                    .ok_or_else(truncated)?;
+                *position += count;
+                for (pixel, &value) in scanline[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads flat pixels, expanding the repeat markers of the original
+/// run-length encoding.
+fn read_flat_scanline(
+    data: &[u8],
+    position: &mut usize,
+    scanline: &mut [[u8; 4]],
+) -> io::Result<()> {
+    let mut x = 0;
+    let mut shift = 0;
+    while x < scanline.len() {
+        let bytes = data.get(*position..*position + 4).ok_or_else(truncated)?;
+        *position += 4;
+        let pixel = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if pixel[..3] == [1, 1, 1] && x > 0 {
+            let count = (pixel[3] as usize) << shift;
+            if x + count > scanline.len() {
+                return Err(invalid_data("run overflows Radiance HDR scanline"));
+            }
+            let previous = scanline[x - 1];
+            for repeated in scanline[x..x + count].iter_mut() {
+                *repeated = previous;
+            }
+            x += count;
+            shift += 8;
+        } else {
+            scanline[x] = pixel;
+            x += 1;
+            shift = 0;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a Radiance RGBE (.hdr) file stored top to bottom, left to right
+/// (the `-Y height +X width` orientation every common tool writes).
+pub fn read_hdr(data: &[u8]) -> io::Result<Image> {
+    let mut position = 0;
+    if !read_line(data, &mut position)?.starts_with("#?") {
+        return Err(invalid_data("not a Radiance HDR file"));
+    }
+    loop {
+        let line = read_line(data, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data(format!(
+                    "unsupported Radiance HDR format '{}'",
+                    format
+                )));
+            }
+        }
+    }
+
+    let resolution = read_line(data, &mut position)?;
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse().ok(), width.parse().ok()),
+        _ => (None, None),
+    };
+    let (width, height): (usize, usize) = match (width, height) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+        _ => {
+            return Err(invalid_data(format!(
+                "unsupported Radiance HDR resolution '{}'",
+                resolution
+            )))
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut scanline = vec![[0; 4]; width];
+    for _ in 0..height {
+        let start = data.get(position..position + 4).ok_or_else(truncated)?;
+        let rle = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+            && start[0] == 2
+            && start[1] == 2
+            && start[2] & 0x80 == 0;
+        if rle {
+            if ((start[2] as usize) << 8 | start[3] as usize) != width {
+                return Err(invalid_data("Radiance HDR scanline width mismatch"));
+            }
+            position += 4;
+            read_rle_scanline(data, &mut position, &mut scanline)?;
+        } else {
+            read_flat_scanline(data, &mut position, &mut scanline)?;
+        }
+        pixels.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+    }
+    Ok(Image::new(width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgbe_encoding() {
+        assert_eq!(to_rgbe(&Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(&Color::new(1.0, 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(to_rgbe(&Color::new(0.75, 0.0, 0.0)), [192, 0, 0, 128]);
+        assert_eq!(to_rgbe(&Color::new(1000.0, 0.0, 0.0))[3], 128 + 10);
+    }
+
+    #[test]
+    fn round_trips_through_reader() {
+        for &width in [3, 20].iter() {
+            let pixels = (0..width * 2)
+                .map(|i| Color::new(i as f64, 0.5, if i % 3 == 0 { 0.0 } else { 0.25 }))
+                .collect();
+            let image = Image::new(width, 2, pixels);
+            let mut out = vec![];
+            write_hdr(&mut out, &image).unwrap();
+
+            let read = read_hdr(&out).unwrap();
+            assert_eq!((read.width, read.height), (width, 2));
+            for (read, written) in read.pixels.iter().zip(image.pixels.iter()) {
+                assert_eq!(to_rgbe(read), to_rgbe(written));
+            }
+        }
+    }
+
+    #[test]
+    fn expands_flat_runs() {
+        let mut data = b"#?RADIANCE\n\n-Y 1 +X 4\n".to_vec();
+        data.extend_from_slice(&[128, 64, 32, 129, 1, 1, 1, 2, 0, 0, 0, 0]);
+        let image = read_hdr(&data).unwrap();
+        assert_eq!(image.pixels[2], Color::new(1.0, 0.5, 0.25));
+        assert_eq!(image.pixels[3], Color::new(0.0, 0.0, 0.0));
+
+        assert!(read_hdr(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn run_length_encodes_channels() {
+        let mut out = vec![];
+        write_rle_channel(&mut out, &[7, 7, 7, 7, 7, 1, 2, 3, 3]).unwrap();
+        assert_eq!(out, vec![128 + 5, 7, 4, 1, 2, 3, 3]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::io;
+
+use super::Image;
+use crate::vec3::Color;
+
+/// Reads a baseline or progressive JPEG file, colour or greyscale. The
+/// stored values are scaled to [0, 1] without undoing their transfer curve.
+pub fn read_jpeg(data: &[u8]) -> io::Result<Image> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let bytes = decoder
+        .decode()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let info = decoder.info().unwrap();
+
+    let value = |byte: &u8| *byte as f64 / 255.0;
+    let pixels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => bytes
+            .iter()
+            .map(|byte| Color::new(value(byte), value(byte), value(byte)))
+            .collect(),
+        jpeg_decoder::PixelFormat::RGB24 => bytes
+            .chunks_exact(3)
+            .map(|rgb| Color::new(value(&rgb[0]), value(&rgb[1]), value(&rgb[2])))
+            .collect(),
+        format => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported JPEG pixel format {:?}", format),
+            ))
+        }
+    };
+    Ok(Image::new(
+        info.width as usize,
+        info.height as usize,
+        pixels,
+    ))
+}
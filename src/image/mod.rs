@@ -0,0 +1,192 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::vec3::*;
+
+mod exr;
+mod hdr;
+mod jpeg;
+mod png;
+mod ppm;
+
+pub use self::exr::*;
+pub use self::hdr::*;
+pub use self::jpeg::*;
+pub use self::png::*;
+pub use self::ppm::*;
+
+/// A rendered picture: one linear radiance value per pixel, stored row by
+/// row starting from the top-left corner.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Image {
+        assert_eq!(pixels.len(), width * height, "wrong number of pixels");
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Converts every pixel to 8-bit RGB, applying the gamma 2 curve the
+    /// renderer has always used for display.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        let encode = |c: f64| (255.99 * c.max(0.0).sqrt()).min(255.0) as u8;
+        self.pixels
+            .iter()
+            .flat_map(|pixel| [encode(pixel.x()), encode(pixel.y()), encode(pixel.z())])
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    /// Radiance RGBE, keeping the linear radiance.
+    Hdr,
+    /// OpenEXR, keeping the linear radiance.
+    Exr(ExrOptions),
+}
+
+impl ImageFormat {
+    /// Guesses the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "hdr" => Some(ImageFormat::Hdr),
+            "exr" => Some(ImageFormat::Exr(ExrOptions::default())),
+            _ => None,
+        }
+    }
+}
+
+/// How the values of 8 and 16-bit images (PNG and JPEG) are encoded.
+/// High dynamic range formats always store linear values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    /// Colours encoded with the sRGB transfer curve, as nearly every
+    /// picture is.
+    Srgb,
+    /// Values to use as stored, such as roughness maps.
+    Linear,
+}
+
+/// Undoes the sRGB transfer curve of a value in [0, 1].
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[derive(Debug)]
+pub struct ImageError {
+    pub path: PathBuf,
+    pub source: io::Error,
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+pub fn write_image(out: &mut dyn Write, image: &Image, format: ImageFormat) -> io::Result<()> {
+    match format {
+        ImageFormat::Ppm => write_ppm(out, image),
+        ImageFormat::Png => write_png(out, image),
+        ImageFormat::Hdr => write_hdr(out, image),
+        ImageFormat::Exr(options) => write_exr(out, image, &options),
+    }
+}
+
+/// Loads a Radiance `.hdr`, OpenEXR `.exr`, `.png` or `.jpg` image as
+/// linear values, decoding those of PNG and JPEG images according to
+/// `color_space`.
+pub fn load_image(path: &Path, color_space: ColorSpace) -> Result<Image, ImageError> {
+    let error = |source| ImageError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let data = fs::read(path).map_err(error)?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    let (image, encoded) = match extension.as_deref() {
+        Some("hdr") => (read_hdr(&data), false),
+        Some("exr") => (read_exr(&data), false),
+        Some("png") => (read_png(&data), true),
+        Some("jpg") | Some("jpeg") => (read_jpeg(&data), true),
+        _ => (
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported image format, expected .hdr, .exr, .png or .jpg",
+            )),
+            false,
+        ),
+    };
+    let mut image = image.map_err(error)?;
+    if image.width == 0 || image.height == 0 {
+        return Err(error(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "image is empty",
+        )));
+    }
+    if encoded && color_space == ColorSpace::Srgb {
+        for pixel in image.pixels.iter_mut() {
+            for channel in 0..3 {
+                pixel[channel] = srgb_to_linear(pixel[channel]);
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// Saves `image` to `path` in the given format.
+pub fn save_image(path: &Path, image: &Image, format: ImageFormat) -> Result<(), ImageError> {
+    let error = |source| ImageError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut out = BufWriter::new(File::create(path).map_err(error)?);
+    write_image(&mut out, image, format).map_err(error)?;
+    out.flush().map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_images() {
+        let path = std::env::temp_dir().join("rustracer-empty-image-test.hdr");
+        for (width, height) in [(0, 0), (0, 3), (3, 0)] {
+            let header = format!(
+                "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+                height, width
+            );
+            fs::write(&path, header).unwrap();
+            let error = load_image(&path, ColorSpace::Linear).err().unwrap();
+            assert_eq!(error.source.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{self, Write};
+
+use super::Image;
+use crate::vec3::Color;
+
+fn to_io_error(error: png::EncodingError) -> io::Error {
+    match error {
+        png::EncodingError::IoError(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+fn from_decoding_error(error: png::DecodingError) -> io::Error {
+    match error {
+        png::DecodingError::IoError(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+/// Writes `image` as an 8-bit RGB PNG file.
+pub fn write_png(out: &mut dyn Write, image: &Image) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(out, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // The pixels are encoded with a plain gamma 2 curve, see `Image::to_rgb8`.
+    encoder.set_source_gamma(png::ScaledFloat::new(0.5));
+    encoder
+        .add_text_chunk("Software".to_string(), "rustracer".to_string())
+        .map_err(to_io_error)?;
+
+    let mut writer = encoder.write_header().map_err(to_io_error)?;
+    writer
+        .write_image_data(&image.to_rgb8())
+        .map_err(to_io_error)?;
+    writer.finish().map_err(to_io_error)
+}
+
+/// Reads a PNG file of any colour type and bit depth. The stored values
+/// are scaled to [0, 1] without undoing their transfer curve; alpha is
+/// ignored.
+pub fn read_png(data: &[u8]) -> io::Result<Image> {
+    let mut decoder = png::Decoder::new(io::Cursor::new(data));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(from_decoding_error)?;
+    let size = reader
+        .output_buffer_size()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PNG image too large"))?;
+    let mut buffer = vec![0; size];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(from_decoding_error)?;
+    let buffer = &buffer[..info.buffer_size()];
+
+    let values: Vec<f64> = match info.bit_depth {
+        png::BitDepth::Sixteen => buffer
+            .chunks_exact(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as f64 / 65535.0)
+            .collect(),
+        _ => buffer.iter().map(|&byte| byte as f64 / 255.0).collect(),
+    };
+    let channels = info.color_type.samples();
+    let pixels = values
+        .chunks_exact(channels)
+        .map(|pixel| match pixel.len() {
+            // Grey, possibly with alpha.
+            1 | 2 => Color::new(pixel[0], pixel[0], pixel[0]),
+            _ => Color::new(pixel[0], pixel[1], pixel[2]),
+        })
+        .collect();
+    Ok(Image::new(
+        info.width as usize,
+        info.height as usize,
+        pixels,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_decoder() {
+        let image = Image::new(
+            3,
+            2,
+            (0..6)
+                .map(|i| Color::new(i as f64 / 5.0, 0.0, 1.0))
+                .collect(),
+        );
+        let mut out = vec![];
+        write_png(&mut out, &image).unwrap();
+
+        let decoder = png::Decoder::new(io::Cursor::new(out));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&buffer[..info.buffer_size()], &image.to_rgb8()[..]);
+    }
+
+    #[test]
+    fn reads_written_image() {
+        let image = Image::new(
+            2,
+            1,
+            vec![Color::new(1.0, 0.25, 0.0), Color::new(0.0, 0.0, 0.0)],
+        );
+        let mut out = vec![];
+        write_png(&mut out, &image).unwrap();
+
+        let read = read_png(&out).unwrap();
+        assert_eq!((read.width, read.height), (2, 1));
+        // The written values are gamma encoded.
+        assert_eq!(read.pixels[0], Color::new(1.0, 127.0 / 255.0, 0.0));
+        assert_eq!(read.pixels[1], Color::new(0.0, 0.0, 0.0));
+    }
+}
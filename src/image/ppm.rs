@@ -0,0 +1,28 @@
+use std::io::{self, Write};
+
+use super::Image;
+
+/// Writes `image` as a binary (P6) PPM file.
+pub fn write_ppm(out: &mut dyn Write, image: &Image) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width, image.height)?;
+    out.write_all(&image.to_rgb8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Color;
+
+    #[test]
+    fn writes_binary_header_and_pixels() {
+        let image = Image::new(
+            2,
+            1,
+            vec![Color::new(1.0, 0.25, 0.0), Color::new(4.0, -1.0, 0.0)],
+        );
+        let mut out = vec![];
+        write_ppm(&mut out, &image).unwrap();
+        assert_eq!(&out[..11], b"P6\n2 1\n255\n");
+        assert_eq!(&out[11..], &[255, 127, 0, 255, 0, 0]);
+    }
+}
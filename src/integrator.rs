@@ -0,0 +1,357 @@
+use crate::materials::Interior;
+use crate::objects::HitRecord;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vec3::*;
+
+// Offset keeping secondary rays from hitting the surface they start on.
+const T_MIN: f64 = 0.001;
+
+/// The power heuristic weighting a sample taken with density `pdf` against
+/// another strategy that would have produced it with density `other`.
+fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let pdf2 = pdf * pdf;
+    let other2 = other * other;
+    if other2 > 0.0 {
+        pdf2 / (pdf2 + other2)
+    } else {
+        1.0
+    }
+}
+
+/// Light reaching `record` directly from the environment, estimated with a
+/// single shadow ray and weighted against scattering from the material.
+fn sample_environment(scene: &Scene, ray: &Ray, record: &HitRecord) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let sample = match scene.environment.sample() {
+        Some(sample) if sample.pdf > 0.0 => sample,
+        _ => return black,
+    };
+    let shadow_ray = Ray::new(record.p, sample.direction).with_time(ray.time());
+    let transmittance = scene.world.transmittance(&shadow_ray, T_MIN, f64::INFINITY);
+    if transmittance == 0.0 {
+        return black;
+    }
+    let reflectance = record.material.eval(ray, record, &sample.direction);
+    let scatter_pdf = record.material.pdf(ray, record, &sample.direction);
+    let weight = power_heuristic(sample.pdf, scatter_pdf);
+    transmittance * reflectance * sample.radiance * weight / sample.pdf
+}
+
+/// Light reaching `record` directly from one of the scene lights, chosen at
+/// random, weighted against scattering from the material.
+fn sample_lights(scene: &Scene, ray: &Ray, record: &HitRecord) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let count = scene.lights.len();
+    if count == 0 {
+        return black;
+    }
+    let light = &scene.lights[((random_f64() * count as f64) as usize).min(count - 1)];
+
+    let sample = match light.sample(&record.p, ray.time()) {
+        Some(sample) if sample.pdf > 0.0 => sample,
+        _ => return black,
+    };
+    let shadow_ray = Ray::new(record.p, sample.direction).with_time(ray.time());
+    let transmittance = scene
+        .world
+        .transmittance(&shadow_ray, T_MIN, sample.distance - T_MIN);
+    if transmittance == 0.0 {
+        return black;
+    }
+    let reflectance = record.material.eval(ray, record, &sample.direction);
+    let light_pdf = sample.pdf / count as f64;
+    // Lights that scattered rays cannot hit are only found here.
+    let weight = if light.is_hittable() {
+        power_heuristic(
+            light_pdf,
+            record.material.pdf(ray, record, &sample.direction),
+        )
+    } else {
+        1.0
+    };
+    transmittance * reflectance * sample.radiance * weight / light_pdf
+}
+
+/// The density with which `sample_lights` picks `point` from `origin`.
+fn lights_pdf(scene: &Scene, origin: &Point3, point: &Point3, time: f64) -> f64 {
+    if scene.lights.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = scene
+        .lights
+        .iter()
+        .map(|light| light.pdf(origin, point, time))
+        .sum();
+    sum / scene.lights.len() as f64
+}
+
+/// Estimates the radiance arriving at the origin of `ray` by following a
+/// single path of at most `max_bounces` scattering events. At every
+/// non-specular surface the lights and the environment are also sampled
+/// directly, and both ways of finding them are combined with multiple
+/// importance sampling.
+pub fn ray_color(ray: Ray, scene: &Scene, max_bounces: i32) -> Color {
+    let mut ray = ray;
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+    // Density with which the previous bounce chose the direction of `ray`,
+    // or `None` for camera rays and specular bounces, where lights were not
+    // sampled directly and whatever the path hits counts in full.
+    let mut scatter_pdf: Option<f64> = None;
+    // The dielectrics the origin of `ray` lies inside.
+    let mut interior = Interior::OUTSIDE;
+
+    for _ in 0..max_bounces {
+        let record = match scene.world.hit(&ray, T_MIN, f64::INFINITY) {
+            Some(record) => HitRecord {
+                interior: &interior,
+                ..record
+            },
+            None => {
+                let weight = match scatter_pdf {
+                    Some(pdf) => power_heuristic(pdf, scene.environment.pdf(ray.direction())),
+                    None => 1.0,
+                };
+                color += weight * throughput * scene.environment.radiance(ray.direction());
+                break;
+            }
+        };
+
+        // Whatever ends the segment, the medium it crossed absorbed some of
+        // the light along it.
+        if let Some(medium) = interior.current() {
+            throughput = throughput * medium.transmittance(record.t * ray.direction().length());
+        }
+
+        let emitted = record.material.emitted(&ray, &record);
+        if emitted != Color::new(0.0, 0.0, 0.0) {
+            let weight = match scatter_pdf {
+                Some(pdf) => {
+                    power_heuristic(pdf, lights_pdf(scene, ray.origin(), &record.p, ray.time()))
+                }
+                None => 1.0,
+            };
+            color += weight * throughput * emitted;
+        }
+
+        let (attenuation, scattered) = match record.material.scatter(&ray, &record) {
+            Some(scattered) => scattered,
+            None => break,
+        };
+
+        scatter_pdf = if record.material.is_specular(&record) {
+            None
+        } else {
+            color += throughput
+                * (sample_lights(scene, &ray, &record) + sample_environment(scene, &ray, &record));
+            Some(record.material.pdf(&ray, &record, scattered.direction()))
+        };
+
+        throughput = throughput * attenuation;
+        let next_interior = record.material.interior(&ray, &record, &scattered);
+        // The footprint keeps growing from its width at the hit, ignoring
+        // how curved surfaces and rough materials widen it further.
+        ray = scattered
+            .with_footprint(ray.footprint(record.t), ray.spread())
+            .with_time(ray.time());
+        interior = next_interior;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::*;
+    use crate::image::Image;
+    use crate::lights::{AreaLight, Light};
+    use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+    use crate::objects::{Hittable, HittableCollection, MovingSphere, Sphere, Triangle};
+    use crate::scene::*;
+    use std::sync::Arc;
+
+    fn sphere_scene(environment: Box<dyn Environment>) -> Scene {
+        Scene {
+            camera: CameraSettings {
+                look_from: Point3::new(0.0, 0.0, 5.0),
+                look_at: Point3::new(0.0, 0.0, 0.0),
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vertical_fov: 40.0,
+                aperture: 0.0,
+                focus_distance: 5.0,
+                shutter_open: 0.0,
+                shutter_close: 0.0,
+            },
+            settings: RenderSettings {
+                width: 1,
+                height: 1,
+                samples_per_pixel: 1,
+                max_bounces: 10,
+            },
+            environment,
+            lights: vec![],
+            world: Box::new(Sphere {
+                center: Point3::new(0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            }),
+        }
+    }
+
+    fn average(scene: &Scene, ray: &Ray, samples: usize) -> Color {
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            let ray = Ray::new(*ray.origin(), *ray.direction());
+            sum += ray_color(ray, scene, 10);
+        }
+        sum / samples as f64
+    }
+
+    #[test]
+    fn sampled_environment_matches_unsampled() {
+        // A convex diffuse object under uniform light L reflects albedo * L.
+        let color = Color::new(2.0, 2.0, 2.0);
+        let image = Image::new(8, 4, vec![color; 32]);
+        let sampled = sphere_scene(Box::new(ImageEnvironment::new(image, 0.0, 1.0)));
+        let unsampled = sphere_scene(Box::new(ConstantEnvironment { color }));
+
+        crate::random::seed(1);
+        let ray = Ray::new(Point3::new(0.3, 0.4, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let sampled = average(&sampled, &ray, 20000);
+        let unsampled = average(&unsampled, &ray, 20000);
+        assert!((sampled.x() - 1.0).abs() < 0.05, "{:?}", sampled);
+        assert!((unsampled.x() - 1.0).abs() < 1e-9, "{:?}", unsampled);
+    }
+
+    /// A floor of the given material lit by a sphere of radiance 4 whose
+    /// top is at height 4, and the ray looking down at the floor from below
+    /// the sphere.
+    fn floor_under_light(floor: Box<dyn Material>) -> (Scene, Ray) {
+        let light = Arc::new(Sphere {
+            center: Point3::new(0.0, 3.0, 0.0),
+            radius: 1.0,
+            material: Box::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0))),
+        });
+        floor_under(light, floor)
+    }
+
+    /// A floor of the given material lit by `light`, and the ray looking
+    /// down at the floor from height 1.
+    fn floor_under(light: Arc<dyn Hittable>, floor: Box<dyn Material>) -> (Scene, Ray) {
+        let mut world = HittableCollection::new();
+        world.add(Box::new(Triangle::new(
+            Point3::new(-100.0, 0.0, -100.0),
+            Point3::new(0.0, 0.0, 200.0),
+            Point3::new(100.0, 0.0, -100.0),
+            floor,
+        )));
+        world.add(Box::new(light.clone()));
+
+        let mut scene = sphere_scene(Box::new(ConstantEnvironment {
+            color: Color::new(0.0, 0.0, 0.0),
+        }));
+        scene.world = Box::new(world);
+        scene.lights = vec![Box::new(AreaLight::new(light)) as Box<dyn Light>];
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.01, -1.0, 0.0));
+        (scene, ray)
+    }
+
+    #[test]
+    fn sampled_lights_match_random_walk() {
+        // A diffuse floor lit by a sphere of radiance L seen under an angle
+        // theta reflects albedo * L * sin(theta)^2 straight under it.
+        let floor = Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let (mut scene, ray) = floor_under_light(floor);
+        let expected = 0.5 * 4.0 / 9.0;
+
+        crate::random::seed(1);
+        let sampled = average(&scene, &ray, 5000).x();
+        assert!((sampled - expected).abs() < 0.005, "{}", sampled);
+        scene.lights.clear();
+        let random_walk = average(&scene, &ray, 50000).x();
+        assert!((random_walk - expected).abs() < 0.02, "{}", random_walk);
+    }
+
+    #[test]
+    fn samples_moving_lights_at_the_time_of_the_ray() {
+        // The light passes over the floor during the exposure.
+        let light: Arc<dyn Hittable> = Arc::new(MovingSphere::new(
+            vec![
+                (0.0, Point3::new(-2.0, 3.0, 0.0)),
+                (1.0, Point3::new(2.0, 3.0, 0.0)),
+            ],
+            1.0,
+            Box::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0))),
+        ));
+        let floor = Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let (mut scene, ray) = floor_under(light, floor);
+        let average = |scene: &Scene, samples: usize| {
+            let mut sum = Color::new(0.0, 0.0, 0.0);
+            for _ in 0..samples {
+                let ray = Ray::new(*ray.origin(), *ray.direction()).with_time(random_f64());
+                sum += ray_color(ray, scene, 10);
+            }
+            sum.x() / samples as f64
+        };
+
+        crate::random::seed(1);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        assert!(scene.lights[0].sample(&origin, 0.5).is_some());
+        let sampled = average(&scene, 5000);
+        scene.lights.clear();
+        let random_walk = average(&scene, 100000);
+        assert!(
+            (sampled - random_walk).abs() < 0.01,
+            "{} {}",
+            sampled,
+            random_walk
+        );
+    }
+
+    #[test]
+    fn glossy_reflection_matches_random_walk() {
+        let floor = Box::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.3));
+        let (mut scene, ray) = floor_under_light(floor);
+
+        crate::random::seed(1);
+        let sampled = average(&scene, &ray, 20000).x();
+        scene.lights.clear();
+        let random_walk = average(&scene, &ray, 20000).x();
+        assert!(sampled > 1.0);
+        assert!(
+            (sampled - random_walk).abs() < 0.03 * sampled,
+            "{} {}",
+            sampled,
+            random_walk
+        );
+    }
+
+    #[test]
+    fn absorbs_up_to_objects_inside_dielectrics() {
+        // A light inside glass that does not bend the light, seen through
+        // 1.5 units of it.
+        let absorption = Color::new(0.2, 1.0, 2.0);
+        let mut world = HittableCollection::new();
+        world.add(Box::new(Sphere {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 2.0,
+            material: Box::new(Dielectric::new(1.0).with_absorption(absorption)),
+        }));
+        world.add(Box::new(Sphere {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 0.5,
+            material: Box::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        }));
+        let mut scene = sphere_scene(Box::new(ConstantEnvironment {
+            color: Color::new(0.0, 0.0, 0.0),
+        }));
+        scene.world = Box::new(world);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let color = ray_color(ray, &scene, 10);
+        let expected = Color::new((-0.3f64).exp(), (-1.5f64).exp(), (-3.0f64).exp());
+        assert!((color - expected).length() < 1e-9, "{:?}", color);
+    }
+}
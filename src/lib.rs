@@ -0,0 +1,16 @@
+pub mod camera;
+pub mod environment;
+pub mod image;
+pub mod integrator;
+pub mod lights;
+pub mod loaders;
+pub mod materials;
+pub mod objects;
+pub mod polynomial;
+pub mod random;
+pub mod ray;
+pub mod sampling;
+pub mod scene;
+pub mod textures;
+pub mod transform;
+pub mod vec3;
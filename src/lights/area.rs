@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use super::traits::{Light, LightSample};
+use crate::objects::Hittable;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+// Range of ray parameters around the sampled point accepted when finding
+// the surface it lies on.
+const SURFACE_EPSILON: f64 = 1e-6;
+
+/// An object with an emissive material, sharing its geometry with the scene.
+pub struct AreaLight {
+    shape: Arc<dyn Hittable>,
+}
+
+impl AreaLight {
+    pub fn new(shape: Arc<dyn Hittable>) -> AreaLight {
+        AreaLight { shape }
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(&self, origin: &Point3, time: f64) -> Option<LightSample> {
+        let (point, pdf) = self.shape.sample(origin, time)?;
+        // The emission depends on the side of the surface and the direction,
+        // so find the surface record at the sampled point.
+        let ray = Ray::new(*origin, point - *origin).with_time(time);
+        let record = self
+            .shape
+            .hit(&ray, 1.0 - SURFACE_EPSILON, 1.0 + SURFACE_EPSILON)?;
+        let distance = ray.direction().length();
+        Some(LightSample {
+            direction: *ray.direction() / distance,
+            distance,
+            radiance: record.material.emitted(&ray, &record),
+            pdf,
+        })
+    }
+
+    fn is_hittable(&self) -> bool {
+        true
+    }
+
+    fn pdf(&self, origin: &Point3, point: &Point3, time: f64) -> f64 {
+        let ray = Ray::new(*origin, *point - *origin).with_time(time);
+        match self
+            .shape
+            .hit(&ray, 1.0 - SURFACE_EPSILON, 1.0 + SURFACE_EPSILON)
+        {
+            Some(record) => self.shape.pdf(origin, &record, time),
+            None => 0.0,
+        }
+    }
+}
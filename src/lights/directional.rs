@@ -0,0 +1,90 @@
+use std::f64::consts::PI;
+
+use super::traits::{Light, LightSample};
+use crate::random::random_f64;
+use crate::sampling::orthonormal_basis;
+use crate::vec3::*;
+
+/// A light infinitely far away, such as the sun, seen as a disk of the
+/// given angular diameter. A diameter of zero gives perfectly sharp
+/// shadows.
+pub struct DirectionalLight {
+    /// Unit vector pointing towards the light.
+    to_light: Vec3,
+    /// Irradiance received by a surface facing the light.
+    irradiance: Color,
+    cos_half_angle: f64,
+}
+
+impl DirectionalLight {
+    /// `direction` is the direction the light travels in and
+    /// `angular_diameter` is in degrees.
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f64) -> DirectionalLight {
+        let half_angle = (0.5 * angular_diameter).clamp(0.0, 90.0).to_radians();
+        DirectionalLight {
+            to_light: -direction.normalize(),
+            irradiance,
+            cos_half_angle: half_angle.cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _origin: &Point3, _time: f64) -> Option<LightSample> {
+        if self.cos_half_angle >= 1.0 {
+            return Some(LightSample {
+                direction: self.to_light,
+                distance: f64::INFINITY,
+                radiance: self.irradiance,
+                pdf: 1.0,
+            });
+        }
+
+        // Uniform direction within the cone subtended by the disk, whose
+        // radiance gives the requested irradiance at normal incidence.
+        let one_minus_cos = 1.0 - self.cos_half_angle;
+        let sin2_half_angle = 1.0 - self.cos_half_angle * self.cos_half_angle;
+        let cos_theta = 1.0 - random_f64() * one_minus_cos;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_f64();
+        let (u, v) = orthonormal_basis(&self.to_light);
+        Some(LightSample {
+            direction: sin_theta * phi.cos() * u
+                + sin_theta * phi.sin() * v
+                + cos_theta * self.to_light,
+            distance: f64::INFINITY,
+            radiance: self.irradiance / (PI * sin2_half_angle),
+            pdf: 1.0 / (2.0 * PI * one_minus_cos),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_irradiance_of_soft_light() {
+        let light =
+            DirectionalLight::new(Vec3::new(0.0, -1.0, 0.0), Color::new(3.0, 3.0, 3.0), 10.0);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+
+        // Monte Carlo estimate of the irradiance on an upward facing surface.
+        let n = 10000;
+        let mut irradiance = 0.0;
+        for _ in 0..n {
+            let sample = light.sample(&origin, 0.0).unwrap();
+            assert!(sample.direction.dot(&up) >= 5f64.to_radians().cos() - 1e-12);
+            assert!(sample.distance.is_infinite());
+            irradiance += sample.radiance.x() * sample.direction.dot(&up) / sample.pdf;
+        }
+        assert!((irradiance / n as f64 - 3.0).abs() < 1e-3);
+
+        let sharp =
+            DirectionalLight::new(Vec3::new(0.0, -2.0, 0.0), Color::new(3.0, 3.0, 3.0), 0.0);
+        let sample = sharp.sample(&origin, 0.0).unwrap();
+        assert_eq!(sample.direction, up);
+        assert_eq!(sample.radiance.x(), 3.0);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod area;
+pub mod directional;
+pub mod point;
+pub mod spot;
+pub mod traits;
+
+pub use area::*;
+pub use directional::*;
+pub use point::*;
+pub use spot::*;
+pub use traits::*;
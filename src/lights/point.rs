@@ -0,0 +1,26 @@
+use super::traits::{Light, LightSample};
+use crate::vec3::*;
+
+/// An infinitely small light emitting `intensity` equally in every
+/// direction, falling off with the square of the distance.
+pub struct PointLight {
+    pub position: Point3,
+    pub intensity: Color,
+}
+
+impl Light for PointLight {
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<LightSample> {
+        let offset = self.position - *origin;
+        let distance_squared = offset.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use super::traits::{Light, LightSample};
+use crate::vec3::*;
+
+/// A point light restricted to a cone. The light is at full intensity
+/// within `inner_angle` of the axis and fades out smoothly up to
+/// `outer_angle`.
+pub struct SpotLight {
+    position: Point3,
+    axis: Vec3,
+    intensity: Color,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    /// Angles are measured from the axis, in degrees.
+    pub fn new(
+        position: Point3,
+        look_at: Point3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> SpotLight {
+        let outer_angle = outer_angle.clamp(0.0, 180.0);
+        SpotLight {
+            position,
+            axis: (look_at - position).normalize(),
+            intensity,
+            cos_inner: inner_angle.clamp(0.0, outer_angle).to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+
+    /// The fraction of the intensity emitted in `direction`.
+    fn falloff(&self, direction: &Vec3) -> f64 {
+        let cosine = direction.dot(&self.axis);
+        if cosine >= self.cos_inner {
+            return 1.0;
+        }
+        if cosine <= self.cos_outer {
+            return 0.0;
+        }
+        let t = (cosine - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<LightSample> {
+        let offset = self.position - *origin;
+        let distance_squared = offset.length_squared();
+        if distance_squared <= 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = offset / distance;
+        let falloff = self.falloff(&-direction);
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (falloff / distance_squared),
+            pdf: 1.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_between_cones() {
+        let light = SpotLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Color::new(8.0, 8.0, 8.0),
+            30.0,
+            45.0,
+        );
+        let radiance = |x: f64| {
+            light
+                .sample(&Point3::new(x, 0.0, 0.0), 0.0)
+                .map_or(0.0, |sample| sample.radiance.x())
+        };
+
+        assert_eq!(radiance(0.0), 2.0);
+        // Inside the inner cone only the distance matters.
+        assert!((radiance(1.0) - 8.0 / 5.0).abs() < 1e-12);
+        assert_eq!(radiance(2.5), 0.0);
+        // Between the cones the intensity follows a smoothstep.
+        let angle = 37.5f64.to_radians();
+        let cos = |degrees: f64| degrees.to_radians().cos();
+        let t = (angle.cos() - cos(45.0)) / (cos(30.0) - cos(45.0));
+        let distance_squared = 4.0 / (angle.cos() * angle.cos());
+        let expected = 8.0 / distance_squared * t * t * (3.0 - 2.0 * t);
+        assert!((radiance(2.0 * angle.tan()) - expected).abs() < 1e-12);
+    }
+}
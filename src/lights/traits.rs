@@ -0,0 +1,36 @@
+use crate::vec3::*;
+
+/// A direction towards a light chosen by `Light::sample`.
+pub struct LightSample {
+    /// Unit vector from the origin of the sample towards the light.
+    pub direction: Vec3,
+    /// Distance to the light along `direction`, infinite for distant lights.
+    pub distance: f64,
+    /// Radiance arriving at the origin of the sample from the light,
+    /// ignoring anything in between.
+    pub radiance: Color,
+    /// Density of `direction` with respect to solid angle, or 1 for lights
+    /// reduced to a single point or direction.
+    pub pdf: f64,
+}
+
+/// A light source that can be sampled directly, instead of being found by
+/// chance when a scattered ray happens to hit it.
+pub trait Light: Sync + Send {
+    /// Picks a direction towards the light as seen from `origin` at `time`,
+    /// or `None` if the light cannot illuminate `origin`.
+    fn sample(&self, origin: &Point3, time: f64) -> Option<LightSample>;
+
+    /// Whether the light has a surface in the scene that scattered rays can
+    /// hit. The light of other lights is only found by sampling them.
+    fn is_hittable(&self) -> bool {
+        false
+    }
+
+    /// The density with which `sample`, called from `origin` at `time`,
+    /// picks the direction towards `point`; zero if `point` does not lie on
+    /// the light.
+    fn pdf(&self, _origin: &Point3, _point: &Point3, _time: f64) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,7 @@
+mod obj;
+mod scene;
+mod vol;
+
+pub use obj::*;
+pub use scene::*;
+pub use vol::*;
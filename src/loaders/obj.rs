@@ -0,0 +1,610 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::image::{load_image, ColorSpace};
+use crate::materials::*;
+use crate::objects::*;
+use crate::textures::*;
+use crate::vec3::*;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    /// The faces read do not make a valid mesh.
+    Mesh {
+        file: String,
+        source: MeshError,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+            ObjError::Mesh { file, source } => write!(f, "{}: {}", file, source),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+            ObjError::Mesh { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Material parameters read from an MTL file, before they are mapped onto
+/// one of the renderer materials.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+    pub diffuse: Color,
+    pub specular: Color,
+    pub emission: Color,
+    pub shininess: f64,
+    pub refraction_index: f64,
+    pub dissolve: f64,
+    pub illum: u32,
+    /// Image given by `map_Kd`, relative to the MTL file until `load_obj`
+    /// resolves it.
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            emission: Color::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+            diffuse_map: None,
+        }
+    }
+}
+
+/// Materials defined by an MTL file, by name.
+pub type MtlLibrary = HashMap<String, MtlMaterial>;
+
+fn max_component(color: &Color) -> f64 {
+    color.x().max(color.y()).max(color.z())
+}
+
+impl MtlMaterial {
+    /// Picks the renderer material closest to the MTL description: emissive
+    /// materials become `DiffuseLight`, transparent materials become
+    /// `Dielectric`, materials that are mostly specular (or use the mirror
+    /// illumination model) become `Metal` with a fuzziness derived from the
+    /// Phong exponent, and everything else is `Lambertian`, textured with the
+    /// `map_Kd` image multiplied by the diffuse colour when there is one.
+    pub fn to_material(&self) -> Result<Box<dyn Material>, ObjError> {
+        if max_component(&self.emission) > 0.0 {
+            return Ok(Box::new(DiffuseLight::new(self.emission)));
+        }
+        if self.dissolve < 1.0 {
+            return Ok(Box::new(Dielectric::new(self.refraction_index)));
+        }
+
+        let specular = max_component(&self.specular);
+        if specular > 0.0 && (self.illum == 3 || specular > max_component(&self.diffuse)) {
+            let fuzziness = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            return Ok(Box::new(Metal::new(self.specular, fuzziness)));
+        }
+
+        if let Some(path) = &self.diffuse_map {
+            let mut image = load_image(path, ColorSpace::Srgb).map_err(|error| ObjError::Io {
+                path: error.path,
+                source: error.source,
+            })?;
+            for pixel in image.pixels.iter_mut() {
+                *pixel = *pixel * self.diffuse;
+            }
+            let texture = ImageTexture::new(image, WrapMode::Repeat);
+            return Ok(Box::new(Lambertian::textured(Arc::new(texture))));
+        }
+        Ok(Box::new(Lambertian::new(self.diffuse)))
+    }
+}
+
+struct LineParser<'a> {
+    file: &'a str,
+    line: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn error(&self, message: impl Into<String>) -> ObjError {
+        ObjError::Parse {
+            file: self.file.to_string(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn floats(
+        &self,
+        keyword: &str,
+        args: &[&str],
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<f64>, ObjError> {
+        if args.len() < min || args.len() > max {
+            return Err(self.error(if min == max {
+                format!(
+                    "'{}' expects {} numbers, found {}",
+                    keyword,
+                    min,
+                    args.len()
+                )
+            } else {
+                format!(
+                    "'{}' expects {} to {} numbers, found {}",
+                    keyword,
+                    min,
+                    max,
+                    args.len()
+                )
+            }));
+        }
+        args.iter()
+            .map(|arg| {
+                arg.parse::<f64>()
+                    .map_err(|_| self.error(format!("invalid number '{}' in '{}'", arg, keyword)))
+            })
+            .collect()
+    }
+
+    fn color(&self, keyword: &str, args: &[&str]) -> Result<Color, ObjError> {
+        let values = self.floats(keyword, args, 1, 3)?;
+        Ok(match values[..] {
+            [v] => Color::new(v, v, v),
+            [r, g, b] => Color::new(r, g, b),
+            _ => return Err(self.error(format!("'{}' expects 1 or 3 numbers", keyword))),
+        })
+    }
+
+    fn float(&self, keyword: &str, args: &[&str]) -> Result<f64, ObjError> {
+        Ok(self.floats(keyword, args, 1, 1)?[0])
+    }
+
+    fn unsigned(&self, keyword: &str, args: &[&str]) -> Result<u32, ObjError> {
+        match args {
+            [arg] => arg.parse().map_err(|_| {
+                self.error(format!(
+                    "invalid unsigned integer '{}' in '{}'",
+                    arg, keyword
+                ))
+            }),
+            _ => Err(self.error(format!(
+                "'{}' expects 1 integer, found {}",
+                keyword,
+                args.len()
+            ))),
+        }
+    }
+
+    /// Resolves a 1-based (or negative, relative) OBJ index into a 0-based one.
+    fn index(&self, token: &str, count: usize, kind: &str) -> Result<usize, ObjError> {
+        let value: i64 = token
+            .parse()
+            .map_err(|_| self.error(format!("invalid {} index '{}'", kind, token)))?;
+        let resolved = if value > 0 {
+            value - 1
+        } else {
+            count as i64 + value
+        };
+        if value == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(self.error(format!(
+                "{} index {} out of range (have {})",
+                kind, value, count
+            )));
+        }
+        Ok(resolved as usize)
+    }
+}
+
+fn statements(source: &str) -> impl Iterator<Item = (usize, &str, Vec<&str>)> {
+    source.lines().enumerate().filter_map(|(number, line)| {
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next()?;
+        Some((number + 1, keyword, tokens.collect()))
+    })
+}
+
+/// The file name of a texture map statement, after its options such as
+/// `-s 2 2 1`. Names may contain spaces.
+fn texture_file(args: &[&str]) -> Option<String> {
+    let mut rest = args;
+    while let Some((option, after)) = rest.split_first() {
+        let count = match *option {
+            // Options followed by up to three numbers.
+            "-o" | "-s" | "-t" => after
+                .iter()
+                .take(3)
+                .take_while(|arg| arg.parse::<f64>().is_ok())
+                .count(),
+            "-mm" => 2,
+            "-blendu" | "-blendv" | "-bm" | "-boost" | "-cc" | "-clamp" | "-imfchan"
+            | "-texres" | "-type" => 1,
+            _ => break,
+        };
+        rest = after.get(count..)?;
+    }
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest.join(" "))
+    }
+}
+
+/// Parses the contents of an MTL file into named material descriptions.
+pub fn parse_mtl(source: &str, file: &str) -> Result<MtlLibrary, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (line, keyword, args) in statements(source) {
+        let parser = LineParser { file, line };
+        if keyword == "newmtl" {
+            if args.len() != 1 {
+                return Err(parser.error("'newmtl' expects a single material name"));
+            }
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+            current = Some((args[0].to_string(), MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => return Err(parser.error(format!("'{}' before any 'newmtl'", keyword))),
+        };
+        match keyword {
+            "Kd" => material.diffuse = parser.color(keyword, &args)?,
+            "Ks" => material.specular = parser.color(keyword, &args)?,
+            "Ke" => material.emission = parser.color(keyword, &args)?,
+            "Ns" => material.shininess = parser.float(keyword, &args)?,
+            "Ni" => material.refraction_index = parser.float(keyword, &args)?,
+            "d" => material.dissolve = parser.float(keyword, &args)?,
+            "Tr" => material.dissolve = 1.0 - parser.float(keyword, &args)?,
+            "illum" => material.illum = parser.unsigned(keyword, &args)?,
+            "map_Kd" => match texture_file(&args) {
+                Some(path) => material.diffuse_map = Some(PathBuf::from(path)),
+                None => return Err(parser.error("'map_Kd' expects a file name")),
+            },
+            // Ambient statements and other maps have no equivalent yet.
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current.take() {
+        materials.insert(name, material);
+    }
+    Ok(materials)
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<Point3>,
+    normals: Vec<Option<Vec3>>,
+    uvs: Vec<Option<(f64, f64)>>,
+    indices: Vec<[usize; 3]>,
+    vertex_ids: HashMap<(usize, Option<usize>, Option<usize>), usize>,
+}
+
+impl MeshBuilder {
+    fn build(self, material: Box<dyn Material>) -> Result<TriangleMesh, MeshError> {
+        // Vertex attributes are only kept if every vertex of the group has them.
+        let normals = self.normals.into_iter().collect::<Option<Vec<_>>>();
+        let uvs = self.uvs.into_iter().collect::<Option<Vec<_>>>();
+        TriangleMesh::new(MeshData {
+            positions: self.positions,
+            normals,
+            uvs,
+            indices: self.indices,
+            material,
+        })
+    }
+}
+
+/// Parses the contents of an OBJ file. Faces are grouped into one
+/// `TriangleMesh` per material; `load_mtl` is called with the argument of
+/// every `mtllib` statement and must return the materials it defines.
+pub fn parse_obj(
+    source: &str,
+    file: &str,
+    load_mtl: &mut dyn FnMut(&str) -> Result<MtlLibrary, ObjError>,
+) -> Result<Vec<TriangleMesh>, ObjError> {
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut materials = HashMap::new();
+
+    // Meshes are kept in the order their material was first used.
+    let mut groups: Vec<(Option<String>, MeshBuilder)> = vec![(None, MeshBuilder::default())];
+    let mut current_group = 0;
+
+    for (line, keyword, args) in statements(source) {
+        let parser = LineParser { file, line };
+        match keyword {
+            "v" => {
+                let v = parser.floats(keyword, &args, 3, 4)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let n = parser.floats(keyword, &args, 3, 3)?;
+                normals.push(Vec3::new(n[0], n[1], n[2]));
+            }
+            "vt" => {
+                let t = parser.floats(keyword, &args, 1, 3)?;
+                uvs.push((t[0], *t.get(1).unwrap_or(&0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(parser.error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        args.len()
+                    )));
+                }
+                let builder = &mut groups[current_group].1;
+                let mut corners = Vec::with_capacity(args.len());
+                for corner in args.iter() {
+                    let mut parts = corner.split('/');
+                    let position =
+                        parser.index(parts.next().unwrap(), positions.len(), "vertex")?;
+                    let uv = match parts.next() {
+                        Some("") | None => None,
+                        Some(token) => {
+                            Some(parser.index(token, uvs.len(), "texture coordinate")?)
+                        }
+                    };
+                    let normal = match parts.next() {
+                        Some("") | None => None,
+                        Some(token) => Some(parser.index(token, normals.len(), "normal")?),
+                    };
+                    if parts.next().is_some() {
+                        return Err(parser.error(format!("malformed face vertex '{}'", corner)));
+                    }
+
+                    let key = (position, uv, normal);
+                    let next_id = builder.positions.len();
+                    let id = *builder.vertex_ids.entry(key).or_insert(next_id);
+                    if id == next_id {
+                        builder.positions.push(positions[position]);
+                        builder.uvs.push(uv.map(|i| uvs[i]));
+                        builder.normals.push(normal.map(|i| normals[i]));
+                    }
+                    corners.push(id);
+                }
+                // Polygons are assumed convex and triangulated as a fan.
+                for i in 1..corners.len() - 1 {
+                    builder
+                        .indices
+                        .push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(parser.error("'mtllib' expects a file name"));
+                }
+                for library in args.iter() {
+                    materials.extend(load_mtl(library)?);
+                }
+            }
+            "usemtl" => {
+                if args.len() != 1 {
+                    return Err(parser.error("'usemtl' expects a single material name"));
+                }
+                let name = args[0].to_string();
+                if !materials.contains_key(&name) {
+                    return Err(parser.error(format!("unknown material '{}'", name)));
+                }
+                current_group = match groups.iter().position(|(n, _)| n.as_ref() == Some(&name)) {
+                    Some(index) => index,
+                    None => {
+                        groups.push((Some(name), MeshBuilder::default()));
+                        groups.len() - 1
+                    }
+                };
+            }
+            // Grouping, smoothing and free-form statements do not affect the
+            // triangle geometry we build.
+            _ => {}
+        }
+    }
+
+    let mut meshes = vec![];
+    for (name, builder) in groups {
+        if builder.indices.is_empty() {
+            continue;
+        }
+        let material = match name {
+            Some(name) => materials[&name].to_material()?,
+            None => MtlMaterial::default().to_material()?,
+        };
+        let mesh = builder.build(material).map_err(|source| ObjError::Mesh {
+            file: file.to_string(),
+            source,
+        })?;
+        meshes.push(mesh);
+    }
+    Ok(meshes)
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Loads an OBJ file, resolving its material libraries relative to the
+/// directory that contains it.
+pub fn load_obj(path: &Path) -> Result<Vec<TriangleMesh>, ObjError> {
+    let source = read_file(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    parse_obj(&source, &path.display().to_string(), &mut |library| {
+        let mtl_path = directory.join(library);
+        let mtl_source = read_file(&mtl_path)?;
+        let mut library = parse_mtl(&mtl_source, &mtl_path.display().to_string())?;
+        let mtl_directory = mtl_path.parent().unwrap_or(directory);
+        for material in library.values_mut() {
+            if let Some(map) = material.diffuse_map.take() {
+                material.diffuse_map = Some(mtl_directory.join(map));
+            }
+        }
+        Ok(library)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    const MTL: &str = "
+newmtl red
+Kd 0.8 0.1 0.1
+
+newmtl mirror
+Kd 0.0 0.0 0.0
+Ks 0.9 0.9 0.9
+Ns 1000
+
+newmtl glass
+Ni 1.33
+d 0.2
+
+newmtl lamp
+Ke 4 4 3
+
+newmtl wood
+Kd 1 1 1
+map_Kd -s 2 2 1 -clamp on textures/light wood.png
+";
+
+    const OBJ: &str = "
+mtllib scene.mtl
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vn 0 0 1
+usemtl red
+f 1//1 2//1 3//1 4//1
+usemtl glass
+f -4 -2 -1
+";
+
+    fn parse_error(source: &str) -> String {
+        match parse_obj(source, "bad.obj", &mut |_| Ok(HashMap::new())) {
+            Ok(_) => panic!("expected a parse error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_materials() {
+        let materials = parse_mtl(MTL, "scene.mtl").unwrap();
+        assert_eq!(materials["red"].diffuse, Color::new(0.8, 0.1, 0.1));
+        assert_eq!(materials["mirror"].shininess, 1000.0);
+        assert_eq!(materials["glass"].refraction_index, 1.33);
+        assert_eq!(materials["glass"].dissolve, 0.2);
+        assert_eq!(materials["lamp"].emission, Color::new(4.0, 4.0, 3.0));
+        assert_eq!(
+            materials["wood"].diffuse_map,
+            Some(PathBuf::from("textures/light wood.png"))
+        );
+    }
+
+    #[test]
+    fn modulates_diffuse_maps() {
+        let path = std::env::temp_dir().join("rustracer-diffuse-map-test.hdr");
+        let image = crate::image::Image::new(1, 1, vec![Color::new(1.0, 0.5, 0.25)]);
+        crate::image::save_image(&path, &image, crate::image::ImageFormat::Hdr).unwrap();
+        let material = MtlMaterial {
+            diffuse: Color::new(0.5, 1.0, 1.0),
+            diffuse_map: Some(path.clone()),
+            ..MtlMaterial::default()
+        }
+        .to_material()
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let record = HitRecord::new(&ray, ray.at(1.0), normal, 1.0, material.as_ref());
+        let (albedo, _) = material.scatter(&ray, &record).unwrap();
+        assert_eq!(albedo, Color::new(0.5, 0.5, 0.25));
+    }
+
+    #[test]
+    fn parses_geometry_grouped_by_material() {
+        let objects = parse_obj(OBJ, "scene.obj", &mut |name| {
+            assert_eq!(name, "scene.mtl");
+            parse_mtl(MTL, name)
+        })
+        .unwrap();
+        assert_eq!(objects.len(), 2);
+
+        let ray = Ray::new(Point3::new(0.5, -0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = objects[0].hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-12);
+        assert!(objects[1].hit(&ray, 0.0, f64::INFINITY).is_none());
+
+        let ray = Ray::new(Point3::new(-0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(objects[1].hit(&ray, 0.0, f64::INFINITY).is_some());
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        assert_eq!(
+            parse_error("v 1 2\n"),
+            "bad.obj:1: 'v' expects 3 to 4 numbers, found 2"
+        );
+
+        assert_eq!(
+            parse_error("v 0 0 0\n\nf 1 2 3\n"),
+            "bad.obj:3: vertex index 2 out of range (have 1)"
+        );
+
+        assert_eq!(
+            parse_error("usemtl missing\n"),
+            "bad.obj:1: unknown material 'missing'"
+        );
+
+        let error = parse_mtl("Kd 1 x 1\n", "bad.mtl").unwrap_err();
+        assert_eq!(error.to_string(), "bad.mtl:1: 'Kd' before any 'newmtl'");
+
+        for illum in ["-1", "2.7", "NaN"] {
+            let source = format!("newmtl glass\nillum {}\n", illum);
+            let error = parse_mtl(&source, "bad.mtl").unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("bad.mtl:2: invalid unsigned integer '{}' in 'illum'", illum)
+            );
+        }
+        assert!(parse_mtl("newmtl glass\nillum 7\n", "bad.mtl").is_ok());
+    }
+}
@@ -0,0 +1,2116 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use super::obj::{load_obj, ObjError};
+use super::vol::load_vol;
+use crate::environment::*;
+use crate::image::{load_image, ColorSpace, ImageError};
+use crate::lights::*;
+use crate::materials::*;
+use crate::objects::*;
+use crate::scene::*;
+use crate::textures::*;
+use crate::transform::Transform;
+use crate::vec3::*;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The file is not valid TOML or does not match the expected layout.
+    Syntax {
+        file: String,
+        source: Box<toml::de::Error>,
+    },
+    /// The file is well formed but describes an invalid scene.
+    Invalid {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    Obj(ObjError),
+    Image(ImageError),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            SceneError::Syntax { file, source } => write!(f, "{}: {}", file, source),
+            SceneError::Invalid {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+            SceneError::Obj(error) => error.fmt(f),
+            SceneError::Image(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Syntax { source, .. } => Some(source),
+            SceneError::Invalid { .. } => None,
+            SceneError::Obj(error) => Some(error),
+            SceneError::Image(error) => Some(error),
+        }
+    }
+}
+
+impl From<ObjError> for SceneError {
+    fn from(error: ObjError) -> SceneError {
+        SceneError::Obj(error)
+    }
+}
+
+impl From<ImageError> for SceneError {
+    fn from(error: ImageError) -> SceneError {
+        SceneError::Image(error)
+    }
+}
+
+fn vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    camera: Spanned<CameraDescription>,
+    render: Option<Spanned<RenderDescription>>,
+    environment: Option<Spanned<EnvironmentDescription>>,
+    /// Deprecated spelling of a `constant` environment of this colour.
+    background: Option<Spanned<[f64; 3]>>,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDescription>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDescription>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDescription>>,
+    #[serde(default)]
+    lights: Vec<Spanned<LightDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_vup")]
+    vup: [f64; 3],
+    vertical_fov: f64,
+    #[serde(default)]
+    aperture: f64,
+    focus_distance: Option<f64>,
+    #[serde(default)]
+    shutter_open: f64,
+    #[serde(default)]
+    shutter_close: f64,
+}
+
+fn default_vup() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenderDescription {
+    #[serde(default = "default_width")]
+    width: usize,
+    height: Option<usize>,
+    #[serde(default = "default_aspect_ratio")]
+    aspect_ratio: f64,
+    #[serde(default = "default_samples_per_pixel")]
+    samples_per_pixel: u32,
+    #[serde(default = "default_max_bounces")]
+    max_bounces: i32,
+}
+
+fn default_width() -> usize {
+    1920
+}
+
+fn default_aspect_ratio() -> f64 {
+    16.0 / 9.0
+}
+
+fn default_samples_per_pixel() -> u32 {
+    500
+}
+
+fn default_max_bounces() -> i32 {
+    50
+}
+
+impl Default for RenderDescription {
+    fn default() -> RenderDescription {
+        RenderDescription {
+            width: default_width(),
+            height: None,
+            aspect_ratio: default_aspect_ratio(),
+            samples_per_pixel: default_samples_per_pixel(),
+            max_bounces: default_max_bounces(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum EnvironmentDescription {
+    Constant {
+        color: [f64; 3],
+    },
+    Gradient {
+        #[serde(default = "default_gradient_bottom")]
+        bottom: [f64; 3],
+        #[serde(default = "default_gradient_top")]
+        top: [f64; 3],
+    },
+    /// Equirectangular image, normally Radiance HDR or OpenEXR; PNG and JPEG
+    /// images are decoded from sRGB.
+    Image {
+        path: String,
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+}
+
+fn default_gradient_bottom() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_gradient_top() -> [f64; 3] {
+    [0.5, 0.7, 1.0]
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+/// A colour given either directly or as the name of a texture.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum ColorParameter {
+    Color([f64; 3]),
+    Texture(String),
+}
+
+/// A number given either directly or as the name of a texture, whose
+/// channels are averaged.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum ScalarParameter {
+    Value(f64),
+    Texture(String),
+}
+
+impl ColorParameter {
+    fn texture(&self) -> Option<&str> {
+        match self {
+            ColorParameter::Color(_) => None,
+            ColorParameter::Texture(name) => Some(name),
+        }
+    }
+}
+
+impl ScalarParameter {
+    fn texture(&self) -> Option<&str> {
+        match self {
+            ScalarParameter::Value(_) => None,
+            ScalarParameter::Texture(name) => Some(name),
+        }
+    }
+}
+
+impl Default for ScalarParameter {
+    fn default() -> ScalarParameter {
+        ScalarParameter::Value(0.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    /// Cubes of side `scale` alternating between `even` and `odd`.
+    Checker {
+        even: ColorParameter,
+        odd: ColorParameter,
+        #[serde(default = "default_checker_scale")]
+        scale: f64,
+    },
+    /// Image mapped over the texture coordinates.
+    Image {
+        path: String,
+        #[serde(default)]
+        wrap: WrapDescription,
+        #[serde(default)]
+        color_space: ColorSpaceDescription,
+    },
+    Noise(NoiseDescription),
+    Turbulence(NoiseDescription),
+    Marble(NoiseDescription),
+    Wood(NoiseDescription),
+}
+
+/// Parameters shared by the procedural textures built on Perlin noise.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoiseDescription {
+    #[serde(default = "default_noise_frequency")]
+    frequency: f64,
+    #[serde(default = "default_noise_octaves")]
+    octaves: u32,
+    #[serde(default)]
+    seed: u64,
+    /// Colours shown for the lowest and highest values of the pattern.
+    #[serde(default = "default_noise_colors")]
+    colors: [[f64; 3]; 2],
+}
+
+fn default_noise_frequency() -> f64 {
+    1.0
+}
+
+fn default_noise_octaves() -> u32 {
+    7
+}
+
+fn default_noise_colors() -> [[f64; 3]; 2] {
+    [[0.0; 3], [1.0; 3]]
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WrapDescription {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl From<WrapDescription> for WrapMode {
+    fn from(wrap: WrapDescription) -> WrapMode {
+        match wrap {
+            WrapDescription::Repeat => WrapMode::Repeat,
+            WrapDescription::Clamp => WrapMode::Clamp,
+            WrapDescription::Mirror => WrapMode::Mirror,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ColorSpaceDescription {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+impl From<ColorSpaceDescription> for ColorSpace {
+    fn from(color_space: ColorSpaceDescription) -> ColorSpace {
+        match color_space {
+            ColorSpaceDescription::Srgb => ColorSpace::Srgb,
+            ColorSpaceDescription::Linear => ColorSpace::Linear,
+        }
+    }
+}
+
+fn default_checker_scale() -> f64 {
+    1.0
+}
+
+fn default_transmittance_distance() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Lambertian {
+        albedo: ColorParameter,
+    },
+    Metal {
+        albedo: ColorParameter,
+        #[serde(default)]
+        fuzziness: ScalarParameter,
+    },
+    /// Glass or liquid, absorbing light inside either by the coefficients
+    /// `absorption` per unit of length or down to `transmittance` after
+    /// `transmittance_distance`.
+    Dielectric {
+        refraction_index: f64,
+        tint: Option<ColorParameter>,
+        absorption: Option<[f64; 3]>,
+        transmittance: Option<[f64; 3]>,
+        #[serde(default = "default_transmittance_distance")]
+        transmittance_distance: f64,
+        /// Which of overlapping dielectrics fills the space they share: the
+        /// one with the highest priority.
+        #[serde(default)]
+        priority: i32,
+    },
+    DiffuseLight {
+        emit: [f64; 3],
+    },
+    /// Medium scattering equally in every direction.
+    Isotropic {
+        albedo: ColorParameter,
+    },
+    /// Medium scattering forwards for positive `g` and backwards for
+    /// negative ones.
+    HenyeyGreenstein {
+        albedo: ColorParameter,
+        #[serde(default)]
+        g: f64,
+    },
+    /// Medium blending a `forward` and a `backward` Henyey-Greenstein lobe,
+    /// the first one by `weight`.
+    DoubleHenyeyGreenstein {
+        albedo: ColorParameter,
+        forward: f64,
+        backward: f64,
+        weight: f64,
+    },
+    /// Medium of particles much smaller than the wavelength, like air.
+    Rayleigh {
+        albedo: ColorParameter,
+    },
+}
+
+impl MaterialDescription {
+    /// Whether the material scatters inside media rather than on surfaces.
+    fn is_phase_function(&self) -> bool {
+        match self {
+            MaterialDescription::Isotropic { .. }
+            | MaterialDescription::HenyeyGreenstein { .. }
+            | MaterialDescription::DoubleHenyeyGreenstein { .. }
+            | MaterialDescription::Rayleigh { .. } => true,
+            MaterialDescription::Lambertian { .. }
+            | MaterialDescription::Metal { .. }
+            | MaterialDescription::Dielectric { .. }
+            | MaterialDescription::DiffuseLight { .. } => false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Sphere whose centre moves through the keyframes while the shutter
+    /// is open.
+    MovingSphere {
+        keyframes: Vec<KeyframeDescription>,
+        radius: f64,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Infinite plane through `point`.
+    Plane {
+        point: [f64; 3],
+        normal: [f64; 3],
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Parallelogram with sides along `u` and `v` from `corner`.
+    Quad {
+        corner: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    Disk {
+        center: [f64; 3],
+        normal: [f64; 3],
+        radius: f64,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Axis-aligned box between two corners.
+    #[serde(rename = "box")]
+    Cuboid {
+        min: [f64; 3],
+        max: [f64; 3],
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Cylinder standing upright on the centre of its `base`.
+    Cylinder {
+        base: [f64; 3],
+        radius: f64,
+        height: f64,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Cone standing upright on the centre of its `base`, truncated when
+    /// `top_radius` is not zero.
+    Cone {
+        base: [f64; 3],
+        bottom_radius: f64,
+        #[serde(default)]
+        top_radius: f64,
+        height: f64,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Ring lying flat around the vertical axis through `center`.
+    Torus {
+        center: [f64; 3],
+        major_radius: f64,
+        minor_radius: f64,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Fog or smoke of uniform `density` filling a convex `boundary`, whose
+    /// material must be a phase function.
+    ConstantMedium {
+        boundary: Box<ObjectDescription>,
+        density: f64,
+    },
+    /// Medium whose density is read from a `.vol` voxel grid and scaled by
+    /// `density`. Files placed several times are loaded once and shared.
+    GridMedium {
+        path: String,
+        #[serde(default = "default_grid_density")]
+        density: f64,
+        material: String,
+        transform: Option<TransformDescription>,
+    },
+    /// Wavefront OBJ file, with materials taken from its MTL libraries.
+    /// Files placed several times are loaded once and shared.
+    Obj {
+        path: String,
+        transform: Option<TransformDescription>,
+    },
+}
+
+fn default_capped() -> bool {
+    true
+}
+
+fn default_grid_density() -> f64 {
+    1.0
+}
+
+impl ObjectDescription {
+    /// Name of the material of a single shape.
+    fn material(&self) -> Option<&str> {
+        match self {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::MovingSphere { material, .. }
+            | ObjectDescription::Triangle { material, .. }
+            | ObjectDescription::Plane { material, .. }
+            | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Disk { material, .. }
+            | ObjectDescription::Cuboid { material, .. }
+            | ObjectDescription::Cylinder { material, .. }
+            | ObjectDescription::Cone { material, .. }
+            | ObjectDescription::Torus { material, .. } => Some(material),
+            ObjectDescription::ConstantMedium { .. }
+            | ObjectDescription::GridMedium { .. }
+            | ObjectDescription::Obj { .. } => None,
+        }
+    }
+}
+
+/// Places an object by scaling it, rotating it around the X, Y and then Z
+/// axes (in degrees) and finally translating it.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDescription {
+    #[serde(default)]
+    scale: ScaleDescription,
+    #[serde(default)]
+    rotate: [f64; 3],
+    #[serde(default)]
+    translate: [f64; 3],
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum ScaleDescription {
+    Uniform(f64),
+    Axes([f64; 3]),
+}
+
+impl Default for ScaleDescription {
+    fn default() -> Self {
+        ScaleDescription::Uniform(1.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDescription {
+    time: f64,
+    center: [f64; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDescription {
+    Point {
+        position: [f64; 3],
+        intensity: [f64; 3],
+    },
+    /// Cone angles are measured from the axis, in degrees.
+    Spot {
+        position: [f64; 3],
+        look_at: [f64; 3],
+        intensity: [f64; 3],
+        #[serde(default)]
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+    /// `direction` is the direction the light travels in.
+    Directional {
+        direction: [f64; 3],
+        irradiance: [f64; 3],
+        #[serde(default)]
+        angular_diameter: f64,
+    },
+}
+
+/// The objects of a scene, along with lights for those that glow and can
+/// be sampled. Glowing shapes that cannot be sampled, such as planes, only
+/// light what scattered rays happen to find them from.
+#[derive(Default)]
+struct SceneObjects {
+    objects: HittableCollection,
+    lights: Vec<Box<dyn Light>>,
+}
+
+impl SceneObjects {
+    /// Adds `object`, sampled directly as an area light if `light` is set.
+    fn add(&mut self, object: impl Hittable + 'static, light: bool) {
+        if light {
+            let object: Arc<dyn Hittable> = Arc::new(object);
+            self.objects.add(Box::new(object.clone()));
+            self.lights.push(Box::new(AreaLight::new(object)));
+        } else {
+            self.objects.add(Box::new(object));
+        }
+    }
+
+    fn place(
+        &mut self,
+        object: impl Hittable + 'static,
+        transform: Option<Transform>,
+        light: bool,
+    ) {
+        match transform {
+            Some(transform) => self.add(Instance::new(Arc::new(object), transform), light),
+            None => self.add(object, light),
+        }
+    }
+}
+
+struct SceneBuilder<'a> {
+    file: &'a str,
+    source: &'a str,
+    directory: &'a Path,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Spanned<MaterialDescription>>,
+    /// The dielectric materials by name, built once so that the objects
+    /// sharing one fill a single medium.
+    dielectrics: HashMap<String, Dielectric>,
+    /// Meshes of the OBJ files loaded so far, by path.
+    meshes: HashMap<PathBuf, Vec<Arc<TriangleMesh>>>,
+    /// Voxel grids loaded so far, by path.
+    grids: HashMap<PathBuf, Arc<VoxelGrid>>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn error(&self, span: Range<usize>, message: impl Into<String>) -> SceneError {
+        let line = self.source[..span.start.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1;
+        SceneError::Invalid {
+            file: self.file.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Builds every texture, resolving the textures they refer to first.
+    fn build_textures(
+        &mut self,
+        descriptions: &HashMap<String, Spanned<TextureDescription>>,
+    ) -> Result<(), SceneError> {
+        let mut names: Vec<&String> = descriptions.keys().collect();
+        names.sort();
+        for name in names {
+            self.build_texture(name, descriptions, &mut vec![])?;
+        }
+        Ok(())
+    }
+
+    fn build_texture(
+        &mut self,
+        name: &str,
+        descriptions: &HashMap<String, Spanned<TextureDescription>>,
+        pending: &mut Vec<String>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let description = &descriptions[name];
+        let span = description.span();
+        if pending.iter().any(|pending| pending == name) {
+            return Err(self.error(span, format!("texture '{}' refers to itself", name)));
+        }
+        pending.push(name.to_string());
+
+        let texture: Arc<dyn Texture> = match description.get_ref() {
+            TextureDescription::Solid { color } => Arc::new(SolidColor::new(vec3(*color))),
+            TextureDescription::Checker { even, odd, scale } => {
+                if *scale <= 0.0 {
+                    return Err(
+                        self.error(span, format!("texture '{}': scale must be positive", name))
+                    );
+                }
+                let mut parameter = |parameter: &ColorParameter| match parameter {
+                    ColorParameter::Color(color) => {
+                        Ok(Arc::new(SolidColor::new(vec3(*color))) as Arc<dyn Texture>)
+                    }
+                    ColorParameter::Texture(other) if descriptions.contains_key(other) => {
+                        self.build_texture(other, descriptions, pending)
+                    }
+                    ColorParameter::Texture(other) => Err(self.error(
+                        span.clone(),
+                        format!("texture '{}': unknown texture '{}'", name, other),
+                    )),
+                };
+                let even = parameter(even)?;
+                let odd = parameter(odd)?;
+                Arc::new(CheckerTexture::new(even, odd, *scale))
+            }
+            TextureDescription::Image {
+                path,
+                wrap,
+                color_space,
+            } => {
+                let image = load_image(&self.directory.join(path), (*color_space).into())?;
+                Arc::new(ImageTexture::new(image, (*wrap).into()))
+            }
+            TextureDescription::Noise(noise) => {
+                self.noise(name, NoisePattern::Noise, noise, span)?
+            }
+            TextureDescription::Turbulence(noise) => {
+                self.noise(name, NoisePattern::Turbulence, noise, span)?
+            }
+            TextureDescription::Marble(noise) => {
+                self.noise(name, NoisePattern::Marble, noise, span)?
+            }
+            TextureDescription::Wood(noise) => self.noise(name, NoisePattern::Wood, noise, span)?,
+        };
+
+        pending.pop();
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    fn noise(
+        &self,
+        name: &str,
+        pattern: NoisePattern,
+        noise: &NoiseDescription,
+        span: Range<usize>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        if noise.frequency <= 0.0 {
+            return Err(self.error(
+                span,
+                format!("texture '{}': frequency must be positive", name),
+            ));
+        }
+        if noise.octaves == 0 {
+            return Err(self.error(
+                span,
+                format!("texture '{}': octaves must be positive", name),
+            ));
+        }
+        Ok(Arc::new(NoiseTexture::new(
+            pattern,
+            noise.seed,
+            noise.frequency,
+            noise.octaves,
+            [vec3(noise.colors[0]), vec3(noise.colors[1])],
+        )))
+    }
+
+    fn texture(&self, name: &str, span: Range<usize>) -> Result<Arc<dyn Texture>, SceneError> {
+        self.textures
+            .get(name)
+            .cloned()
+            .ok_or_else(|| self.error(span, format!("unknown texture '{}'", name)))
+    }
+
+    fn color_parameter(
+        &self,
+        parameter: &ColorParameter,
+        span: Range<usize>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match parameter {
+            ColorParameter::Color(color) => Ok(Arc::new(SolidColor::new(vec3(*color)))),
+            ColorParameter::Texture(name) => self.texture(name, span),
+        }
+    }
+
+    fn scalar_parameter(
+        &self,
+        parameter: &ScalarParameter,
+        span: Range<usize>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match *parameter {
+            ScalarParameter::Value(value) => {
+                Ok(Arc::new(SolidColor::new(Color::new(value, value, value))))
+            }
+            ScalarParameter::Texture(ref name) => self.texture(name, span),
+        }
+    }
+
+    fn material(&self, name: &str, span: Range<usize>) -> Result<Box<dyn Material>, SceneError> {
+        let description = self
+            .materials
+            .get(name)
+            .ok_or_else(|| self.error(span, format!("unknown material '{}'", name)))?;
+        let span = description.span();
+        Ok(match description.get_ref() {
+            MaterialDescription::Lambertian { albedo } => {
+                Box::new(Lambertian::textured(self.color_parameter(albedo, span)?))
+            }
+            MaterialDescription::Metal { albedo, fuzziness } => Box::new(Metal::textured(
+                self.color_parameter(albedo, span.clone())?,
+                self.scalar_parameter(fuzziness, span)?,
+            )),
+            MaterialDescription::Dielectric { .. } => Box::new(self.dielectrics[name].clone()),
+            MaterialDescription::DiffuseLight { emit } => Box::new(DiffuseLight::new(vec3(*emit))),
+            MaterialDescription::Isotropic { albedo } => {
+                Box::new(Isotropic::textured(self.color_parameter(albedo, span)?))
+            }
+            MaterialDescription::HenyeyGreenstein { albedo, g } => Box::new(Anisotropic::textured(
+                self.color_parameter(albedo, span)?,
+                Arc::new(HenyeyGreenstein { g: *g }),
+            )),
+            MaterialDescription::DoubleHenyeyGreenstein {
+                albedo,
+                forward,
+                backward,
+                weight,
+            } => Box::new(Anisotropic::textured(
+                self.color_parameter(albedo, span)?,
+                Arc::new(DoubleHenyeyGreenstein {
+                    forward: *forward,
+                    backward: *backward,
+                    weight: *weight,
+                }),
+            )),
+            MaterialDescription::Rayleigh { albedo } => Box::new(Anisotropic::textured(
+                self.color_parameter(albedo, span)?,
+                Arc::new(Rayleigh),
+            )),
+        })
+    }
+
+    /// Builds the material `name` of a medium, described by `what` in
+    /// errors, which must be a phase function.
+    fn phase_function(
+        &self,
+        what: &str,
+        name: &str,
+        span: Range<usize>,
+    ) -> Result<Box<dyn Material>, SceneError> {
+        let phase = self.material(name, span.clone())?;
+        if self.materials[name].get_ref().is_phase_function() {
+            Ok(phase)
+        } else {
+            Err(self.error(
+                span,
+                format!("{} material '{}' must be a phase function", what, name),
+            ))
+        }
+    }
+
+    fn build_dielectrics(&mut self) -> Result<(), SceneError> {
+        let mut dielectrics = HashMap::new();
+        for (name, description) in self.materials.iter() {
+            if let MaterialDescription::Dielectric {
+                refraction_index,
+                tint,
+                absorption,
+                transmittance,
+                transmittance_distance,
+                priority,
+            } = description.get_ref()
+            {
+                let mut dielectric = match tint {
+                    Some(tint) => Dielectric::tinted(
+                        *refraction_index,
+                        self.color_parameter(tint, description.span())?,
+                    ),
+                    None => Dielectric::new(*refraction_index),
+                };
+                if let Some(absorption) = absorption {
+                    dielectric = dielectric.with_absorption(vec3(*absorption));
+                }
+                if let Some(transmittance) = transmittance {
+                    dielectric = dielectric
+                        .with_transmittance(vec3(*transmittance), *transmittance_distance);
+                }
+                dielectrics.insert(name.clone(), dielectric.with_priority(*priority));
+            }
+        }
+        self.dielectrics = dielectrics;
+        Ok(())
+    }
+
+    fn check_materials(&self) -> Result<(), SceneError> {
+        for (name, description) in self.materials.iter() {
+            let textures = match description.get_ref() {
+                MaterialDescription::Lambertian { albedo }
+                | MaterialDescription::Isotropic { albedo }
+                | MaterialDescription::Rayleigh { albedo } => vec![albedo.texture()],
+                MaterialDescription::HenyeyGreenstein { albedo, g } => {
+                    if !(-1.0 < *g && *g < 1.0) {
+                        return Err(self.error(
+                            description.span(),
+                            format!("material '{}': g must be between -1 and 1", name),
+                        ));
+                    }
+                    vec![albedo.texture()]
+                }
+                MaterialDescription::DoubleHenyeyGreenstein {
+                    albedo,
+                    forward,
+                    backward,
+                    weight,
+                } => {
+                    if [forward, backward].iter().any(|&&g| !(-1.0 < g && g < 1.0)) {
+                        return Err(self.error(
+                            description.span(),
+                            format!(
+                                "material '{}': forward and backward must be between -1 and 1",
+                                name
+                            ),
+                        ));
+                    }
+                    if !(0.0..=1.0).contains(weight) {
+                        return Err(self.error(
+                            description.span(),
+                            format!("material '{}': weight must be between 0 and 1", name),
+                        ));
+                    }
+                    vec![albedo.texture()]
+                }
+                MaterialDescription::Metal { albedo, fuzziness } => {
+                    vec![albedo.texture(), fuzziness.texture()]
+                }
+                MaterialDescription::Dielectric {
+                    refraction_index,
+                    tint,
+                    absorption,
+                    transmittance,
+                    transmittance_distance,
+                    ..
+                } => {
+                    let invalid = if *refraction_index <= 0.0 {
+                        Some("refraction_index must be positive")
+                    } else if absorption.is_some() && transmittance.is_some() {
+                        Some("absorption and transmittance cannot both be given")
+                    } else if absorption.is_some_and(|a| a.iter().any(|&a| a < 0.0)) {
+                        Some("absorption must not be negative")
+                    } else if transmittance.is_some_and(|t| t.iter().any(|&t| t <= 0.0 || t > 1.0))
+                    {
+                        Some("transmittance must be above 0 and at most 1")
+                    } else if *transmittance_distance <= 0.0 {
+                        Some("transmittance_distance must be positive")
+                    } else {
+                        None
+                    };
+                    if let Some(message) = invalid {
+                        return Err(self.error(
+                            description.span(),
+                            format!("material '{}': {}", name, message),
+                        ));
+                    }
+                    vec![tint.as_ref().and_then(ColorParameter::texture)]
+                }
+                MaterialDescription::DiffuseLight { .. } => vec![],
+            };
+            for texture in textures.into_iter().flatten() {
+                if !self.textures.contains_key(texture) {
+                    return Err(self.error(
+                        description.span(),
+                        format!("material '{}': unknown texture '{}'", name, texture),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn transform(
+        &self,
+        transform: Option<TransformDescription>,
+        span: Range<usize>,
+    ) -> Result<Option<Transform>, SceneError> {
+        let transform = match transform {
+            Some(transform) => transform,
+            None => return Ok(None),
+        };
+        let scale = match transform.scale {
+            ScaleDescription::Uniform(scale) => [scale; 3],
+            ScaleDescription::Axes(scale) => scale,
+        };
+        if scale.contains(&0.0) {
+            return Err(self.error(span, "transform scale must not be zero"));
+        }
+        let axes = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        let rotation = axes
+            .iter()
+            .zip(transform.rotate.iter())
+            .fold(Transform::identity(), |rotation, (axis, &degrees)| {
+                rotation.then(&Transform::rotation(axis, degrees))
+            });
+        Ok(Some(
+            Transform::scaling(&vec3(scale))
+                .then(&rotation)
+                .then(&Transform::translation(&vec3(transform.translate))),
+        ))
+    }
+
+    fn object(
+        &mut self,
+        object: ObjectDescription,
+        span: Range<usize>,
+        objects: &mut SceneObjects,
+    ) -> Result<(), SceneError> {
+        match object {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if radius == 0.0 {
+                    return Err(self.error(span, "sphere radius must not be zero"));
+                }
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let sphere = Sphere {
+                    center: vec3(center),
+                    radius,
+                    material,
+                };
+                objects.place(sphere, transform, emissive);
+            }
+            ObjectDescription::MovingSphere {
+                keyframes,
+                radius,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if radius == 0.0 {
+                    return Err(self.error(span, "sphere radius must not be zero"));
+                }
+                if keyframes.is_empty() {
+                    return Err(self.error(span, "moving sphere needs at least one keyframe"));
+                }
+                if keyframes
+                    .windows(2)
+                    .any(|pair| pair[0].time >= pair[1].time)
+                {
+                    return Err(self.error(span, "moving sphere keyframe times must increase"));
+                }
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let keyframes = keyframes
+                    .iter()
+                    .map(|keyframe| (keyframe.time, vec3(keyframe.center)))
+                    .collect();
+                let sphere = MovingSphere::new(keyframes, radius, material);
+                objects.place(sphere, transform, emissive);
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let triangle = Triangle::new(
+                    vec3(vertices[0]),
+                    vec3(vertices[1]),
+                    vec3(vertices[2]),
+                    material,
+                );
+                objects.place(triangle, transform, emissive);
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if normal == [0.0; 3] {
+                    return Err(self.error(span, "plane normal must not be zero"));
+                }
+                let material = self.material(&material, span)?;
+                // Unbounded, so it cannot be sampled as a light.
+                let plane = Plane::new(vec3(point), vec3(normal), material);
+                objects.place(plane, transform, false);
+            }
+            ObjectDescription::Quad {
+                corner,
+                u,
+                v,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if vec3(u).cross(&vec3(v)).near_zero() {
+                    return Err(self.error(span, "quad sides u and v must not be parallel"));
+                }
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let quad = Quad::new(vec3(corner), vec3(u), vec3(v), material);
+                objects.place(quad, transform, emissive);
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if normal == [0.0; 3] {
+                    return Err(self.error(span, "disk normal must not be zero"));
+                }
+                if radius <= 0.0 {
+                    return Err(self.error(span, "disk radius must be positive"));
+                }
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let disk = Disk::new(vec3(center), vec3(normal), radius, material);
+                objects.place(disk, transform, emissive);
+            }
+            ObjectDescription::Cuboid {
+                min,
+                max,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if (0..3).any(|axis| min[axis] >= max[axis]) {
+                    return Err(self.error(span, "box min must be below max on every axis"));
+                }
+                let material = self.material(&material, span)?;
+                let emissive = material.is_emissive();
+                let cuboid = Cuboid::new(vec3(min), vec3(max), material);
+                objects.place(cuboid, transform, emissive);
+            }
+            ObjectDescription::Cylinder {
+                base,
+                radius,
+                height,
+                capped,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if radius <= 0.0 || height <= 0.0 {
+                    return Err(self.error(span, "cylinder radius and height must be positive"));
+                }
+                let material = self.material(&material, span)?;
+                // Quadrics and tori cannot be sampled as lights.
+                let cylinder = Cylinder {
+                    base: vec3(base),
+                    radius,
+                    height,
+                    capped,
+                    material,
+                };
+                objects.place(cylinder, transform, false);
+            }
+            ObjectDescription::Cone {
+                base,
+                bottom_radius,
+                top_radius,
+                height,
+                capped,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if height <= 0.0 {
+                    return Err(self.error(span, "cone height must be positive"));
+                }
+                if bottom_radius < 0.0 || top_radius < 0.0 || bottom_radius + top_radius == 0.0 {
+                    return Err(self.error(
+                        span,
+                        "cone radii must not be negative and one of them must be positive",
+                    ));
+                }
+                let material = self.material(&material, span)?;
+                let cone = Cone {
+                    base: vec3(base),
+                    bottom_radius,
+                    top_radius,
+                    height,
+                    capped,
+                    material,
+                };
+                objects.place(cone, transform, false);
+            }
+            ObjectDescription::Torus {
+                center,
+                major_radius,
+                minor_radius,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if minor_radius <= 0.0 || major_radius <= minor_radius {
+                    return Err(self.error(
+                        span,
+                        "torus radii must satisfy 0 < minor_radius < major_radius",
+                    ));
+                }
+                let material = self.material(&material, span)?;
+                let torus = Torus {
+                    center: vec3(center),
+                    major_radius,
+                    minor_radius,
+                    material,
+                };
+                objects.place(torus, transform, false);
+            }
+            ObjectDescription::ConstantMedium { boundary, density } => {
+                if density <= 0.0 {
+                    return Err(self.error(span, "constant medium density must be positive"));
+                }
+                let material = boundary.material().map(str::to_string).ok_or_else(|| {
+                    self.error(
+                        span.clone(),
+                        "constant medium boundary must be a single shape",
+                    )
+                })?;
+                let phase =
+                    self.phase_function("constant medium boundary", &material, span.clone())?;
+                let mut shape = SceneObjects::default();
+                self.object(*boundary, span.clone(), &mut shape)?;
+                // Rays never find both sides of an unbounded shape, so the
+                // medium would not show.
+                if shape.objects.bounding_box().is_none() {
+                    return Err(self.error(span, "constant medium boundary must be bounded"));
+                }
+                let medium = ConstantMedium::new(Box::new(shape.objects), density, phase);
+                objects.add(medium, false);
+            }
+            ObjectDescription::GridMedium {
+                path,
+                density,
+                material,
+                transform,
+            } => {
+                let transform = self.transform(transform, span.clone())?;
+                if density <= 0.0 {
+                    return Err(self.error(span, "grid medium density must be positive"));
+                }
+                let phase = self.phase_function("grid medium", &material, span)?;
+                let path = self.directory.join(path);
+                if !self.grids.contains_key(&path) {
+                    let grid = load_vol(&path).map_err(|source| SceneError::Io {
+                        path: path.clone(),
+                        source,
+                    })?;
+                    self.grids.insert(path.clone(), Arc::new(grid));
+                }
+                let medium = GridMedium::new(self.grids[&path].clone(), density, phase);
+                objects.place(medium, transform, false);
+            }
+            ObjectDescription::Obj { path, transform } => {
+                let transform = self.transform(transform, span)?;
+                let path = self.directory.join(path);
+                if !self.meshes.contains_key(&path) {
+                    let meshes = load_obj(&path)?.into_iter().map(Arc::new).collect();
+                    self.meshes.insert(path.clone(), meshes);
+                }
+                for mesh in self.meshes[&path].iter() {
+                    let emissive = mesh.data().material.is_emissive();
+                    match transform {
+                        Some(transform) => {
+                            objects.add(Instance::new(mesh.clone(), transform), emissive)
+                        }
+                        None => objects.add(mesh.clone(), emissive),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn light(&self, light: Spanned<LightDescription>) -> Result<Box<dyn Light>, SceneError> {
+        let span = light.span();
+        Ok(match light.into_inner() {
+            LightDescription::Point {
+                position,
+                intensity,
+            } => Box::new(PointLight {
+                position: vec3(position),
+                intensity: vec3(intensity),
+            }),
+            LightDescription::Spot {
+                position,
+                look_at,
+                intensity,
+                inner_angle,
+                outer_angle,
+            } => {
+                if position == look_at {
+                    return Err(self.error(span, "spot light position and look_at must differ"));
+                }
+                if !(0.0 <= inner_angle && inner_angle <= outer_angle && outer_angle <= 180.0) {
+                    return Err(self.error(
+                        span,
+                        "spot light angles must satisfy 0 <= inner_angle <= outer_angle <= 180",
+                    ));
+                }
+                Box::new(SpotLight::new(
+                    vec3(position),
+                    vec3(look_at),
+                    vec3(intensity),
+                    inner_angle,
+                    outer_angle,
+                ))
+            }
+            LightDescription::Directional {
+                direction,
+                irradiance,
+                angular_diameter,
+            } => {
+                if direction == [0.0; 3] {
+                    return Err(self.error(span, "directional light direction must not be zero"));
+                }
+                if !(0.0..180.0).contains(&angular_diameter) {
+                    return Err(self.error(
+                        span,
+                        "directional light angular_diameter must be between 0 and 180",
+                    ));
+                }
+                Box::new(DirectionalLight::new(
+                    vec3(direction),
+                    vec3(irradiance),
+                    angular_diameter,
+                ))
+            }
+        })
+    }
+
+    fn camera(&self, camera: Spanned<CameraDescription>) -> Result<CameraSettings, SceneError> {
+        let span = camera.span();
+        let camera = camera.into_inner();
+        let look_from = vec3(camera.look_from);
+        let look_at = vec3(camera.look_at);
+        if look_from == look_at {
+            return Err(self.error(span, "camera look_from and look_at must differ"));
+        }
+        if !(camera.vertical_fov > 0.0 && camera.vertical_fov < 180.0) {
+            return Err(self.error(span, "camera vertical_fov must be between 0 and 180"));
+        }
+        if camera.shutter_close < camera.shutter_open {
+            return Err(self.error(span, "camera shutter_close must not be before shutter_open"));
+        }
+        Ok(CameraSettings {
+            look_from,
+            look_at,
+            vup: vec3(camera.vup),
+            vertical_fov: camera.vertical_fov,
+            aperture: camera.aperture,
+            focus_distance: camera
+                .focus_distance
+                .unwrap_or_else(|| (look_from - look_at).length()),
+            shutter_open: camera.shutter_open,
+            shutter_close: camera.shutter_close,
+        })
+    }
+
+    fn environment(
+        &self,
+        environment: Option<Spanned<EnvironmentDescription>>,
+        background: Option<Spanned<[f64; 3]>>,
+    ) -> Result<Box<dyn Environment>, SceneError> {
+        let environment = match (environment, background) {
+            (Some(_), Some(background)) => {
+                return Err(self.error(
+                    background.span(),
+                    "background and [environment] cannot both be given",
+                ))
+            }
+            (Some(environment), None) => environment,
+            (None, Some(background)) => {
+                return Ok(Box::new(ConstantEnvironment {
+                    color: vec3(background.into_inner()),
+                }))
+            }
+            (None, None) => return Ok(Box::new(GradientEnvironment::default())),
+        };
+        let span = environment.span();
+        Ok(match environment.into_inner() {
+            EnvironmentDescription::Constant { color } => {
+                Box::new(ConstantEnvironment { color: vec3(color) })
+            }
+            EnvironmentDescription::Gradient { bottom, top } => Box::new(GradientEnvironment {
+                bottom: vec3(bottom),
+                top: vec3(top),
+            }),
+            EnvironmentDescription::Image {
+                path,
+                rotation,
+                intensity,
+            } => {
+                if intensity < 0.0 {
+                    return Err(self.error(span, "environment intensity must not be negative"));
+                }
+                let image = load_image(&self.directory.join(path), ColorSpace::Srgb)?;
+                Box::new(ImageEnvironment::new(image, rotation, intensity))
+            }
+        })
+    }
+
+    fn settings(
+        &self,
+        render: Option<Spanned<RenderDescription>>,
+    ) -> Result<RenderSettings, SceneError> {
+        let (span, render) = match render {
+            Some(render) => (render.span(), render.into_inner()),
+            None => (0..0, RenderDescription::default()),
+        };
+        let height = match render.height {
+            Some(height) => height,
+            None if render.aspect_ratio > 0.0 => {
+                (render.width as f64 / render.aspect_ratio) as usize
+            }
+            None => return Err(self.error(span, "render aspect_ratio must be positive")),
+        };
+        if render.width == 0 || height == 0 {
+            return Err(self.error(span, "render width and height must be positive"));
+        }
+        if render.samples_per_pixel == 0 {
+            return Err(self.error(span, "render samples_per_pixel must be positive"));
+        }
+        Ok(RenderSettings {
+            width: render.width,
+            height,
+            samples_per_pixel: render.samples_per_pixel,
+            max_bounces: render.max_bounces,
+        })
+    }
+}
+
+/// Parses a TOML scene description. Relative paths inside the scene (such
+/// as OBJ files) are resolved against `directory`.
+pub fn parse_scene(source: &str, file: &str, directory: &Path) -> Result<Scene, SceneError> {
+    let description: SceneDescription =
+        toml::from_str(source).map_err(|error| SceneError::Syntax {
+            file: file.to_string(),
+            source: Box::new(error),
+        })?;
+
+    let mut builder = SceneBuilder {
+        file,
+        source,
+        directory,
+        textures: HashMap::new(),
+        materials: description.materials,
+        dielectrics: HashMap::new(),
+        meshes: HashMap::new(),
+        grids: HashMap::new(),
+    };
+    builder.build_textures(&description.textures)?;
+    builder.check_materials()?;
+    builder.build_dielectrics()?;
+
+    let camera = builder.camera(description.camera)?;
+    let settings = builder.settings(description.render)?;
+    let environment = builder.environment(description.environment, description.background)?;
+
+    let mut objects = SceneObjects::default();
+    for object in description.objects {
+        let span = object.span();
+        builder.object(object.into_inner(), span, &mut objects)?;
+    }
+    for light in description.lights {
+        objects.lights.push(builder.light(light)?);
+    }
+
+    Ok(Scene {
+        camera,
+        settings,
+        environment,
+        lights: objects.lights,
+        world: Box::new(Bvh::from(objects.objects)),
+    })
+}
+
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+    let source = fs::read_to_string(path).map_err(|source| SceneError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    parse_scene(&source, &path.display().to_string(), directory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    fn parse(source: &str) -> Result<Scene, SceneError> {
+        parse_scene(source, "test.toml", Path::new("."))
+    }
+
+    fn error(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_example_scene() {
+        let scene = parse(include_str!("../../scenes/three_spheres.toml")).unwrap();
+        assert_eq!(scene.settings.width, 800);
+        assert_eq!(scene.settings.height, 450);
+        assert_eq!(scene.camera.look_from, Point3::new(13.0, 2.0, 3.0));
+
+        let ray = Ray::new(Point3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.t - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_lights_and_environment() {
+        let scene = load_scene(Path::new("scenes/cornell_box.toml")).unwrap();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(scene.environment.radiance(&up), Color::new(0.0, 0.0, 0.0));
+
+        // Looking up at the lamp from the middle of the box.
+        let ray = Ray::new(Point3::new(278.0, 300.0, 278.0), Vec3::new(0.0, 1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.t - 254.0).abs() < 1e-9);
+        assert_eq!(
+            record.material.emitted(&ray, &record),
+            Color::new(15.0, 15.0, 15.0)
+        );
+    }
+
+    #[test]
+    fn accepts_deprecated_background() {
+        let source = "background = [0.1, 0.2, 0.3]\n\
+                      [camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n";
+        let scene = parse(source).unwrap();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(scene.environment.radiance(&up), Color::new(0.1, 0.2, 0.3));
+
+        let message = error(&format!("{}[environment]\ntype = \"gradient\"\n", source));
+        assert_eq!(
+            message,
+            "test.toml:1: background and [environment] cannot both be given"
+        );
+    }
+
+    #[test]
+    fn places_instances() {
+        let scene = load_scene(Path::new("scenes/cornell_boxes.toml")).unwrap();
+        // Looking down at the middle of the top of the short box.
+        let ray = Ray::new(Point3::new(183.0, 500.0, 169.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.t - 335.0).abs() < 1e-9);
+        assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+
+        let scene = parse(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+             [[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"red\"\n\
+             transform = { scale = [2, 1, 1], rotate = [0, 0, 90], translate = [0, 0, -5] }\n",
+        )
+        .unwrap();
+        // Stretched along X, then turned upright.
+        let ray = Ray::new(Point3::new(0.0, 10.0, -5.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.t - 8.0).abs() < 1e-9);
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [[objects]]\ntype = \"obj\"\npath = \"missing.obj\"\n\
+             transform = { scale = [1, 0, 1] }\n",
+        );
+        assert_eq!(message, "test.toml:5: transform scale must not be zero");
+    }
+
+    #[test]
+    fn parses_flat_primitives() {
+        let scene = parse(include_str!("../../scenes/primitives.toml")).unwrap();
+        // Only the quad lamp glows.
+        assert_eq!(scene.lights.len(), 1);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let sample = scene.lights[0].sample(&origin, 0.0).unwrap();
+        assert!(sample.direction.y() > 0.0);
+
+        // Glowing boxes are lights, glowing planes are only seen.
+        let lamps = parse(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.lamp]\ntype = \"diffuse_light\"\nemit = [1, 1, 1]\n\
+             [[objects]]\ntype = \"box\"\nmin = [0, 2, 0]\nmax = [1, 3, 1]\nmaterial = \"lamp\"\n\
+             [[objects]]\ntype = \"plane\"\npoint = [0, 5, 0]\nnormal = [0, -1, 0]\n\
+             material = \"lamp\"\n",
+        )
+        .unwrap();
+        assert_eq!(lamps.lights.len(), 1);
+        let sample = lamps.lights[0].sample(&origin, 0.0).unwrap();
+        assert!(sample.pdf > 0.0);
+        assert_eq!(sample.radiance, Color::new(1.0, 1.0, 1.0));
+
+        // Far away the floor is still flat.
+        let ray = Ray::new(Point3::new(500.0, 1.0, -500.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 1.0);
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+             [[objects]]\ntype = \"quad\"\ncorner = [0, 0, 0]\nu = [1, 0, 0]\nv = [2, 0, 0]\n\
+             material = \"red\"\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:8: quad sides u and v must not be parallel"
+        );
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+             [[objects]]\ntype = \"box\"\nmin = [0, 0, 0]\nmax = [1, 0, 1]\nmaterial = \"red\"\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:8: box min must be below max on every axis"
+        );
+    }
+
+    #[test]
+    fn parses_quadrics() {
+        let scene = parse(include_str!("../../scenes/quadrics.toml")).unwrap();
+        // Down onto the pedestal, just behind the upright ring.
+        let ray = Ray::new(Point3::new(0.5, 5.0, -0.3), Vec3::new(0.0, -1.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-9);
+        // Through the open pipe along its axis, on to the side of the cone.
+        let ray = Ray::new(Point3::new(-10.0, 0.6, -1.0), Vec3::new(1.0, 0.0, 0.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((record.p - Point3::new(2.44, 0.6, -1.0)).length() < 1e-9);
+
+        // A glowing cylinder is seen but not sampled as a light.
+        let scene = parse(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.lamp]\ntype = \"diffuse_light\"\nemit = [1, 1, 1]\n\
+             [[objects]]\ntype = \"cylinder\"\nbase = [0, 0, -5]\nradius = 1\nheight = 2\n\
+             material = \"lamp\"\n",
+        )
+        .unwrap();
+        assert!(scene.lights.is_empty());
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(scene.world.hit(&ray, 0.001, f64::INFINITY).is_some());
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+             [[objects]]\ntype = \"torus\"\ncenter = [0, 0, 0]\nmajor_radius = 1\n\
+             minor_radius = 2\nmaterial = \"red\"\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:8: torus radii must satisfy 0 < minor_radius < major_radius"
+        );
+    }
+
+    #[test]
+    fn parses_constant_media() {
+        crate::random::seed(1);
+        let scene = load_scene(Path::new("scenes/fog.toml")).unwrap();
+        // Most rays crossing the smoke box scatter inside it, short of the
+        // back wall.
+        let ray = Ray::new(Point3::new(350.0, 165.0, -800.0), Vec3::new(0.0, 0.0, 1.0));
+        let scattered = (0..1000)
+            .filter_map(|_| scene.world.hit(&ray, 0.001, f64::INFINITY))
+            .filter(|record| record.t < 1355.0)
+            .count();
+        assert!(scattered > 700, "{}", scattered);
+
+        let source = "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                      [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+                      [[objects]]\ntype = \"constant_medium\"\ndensity = 1\n\
+                      boundary = { type = \"sphere\", center = [0, 0, 0], radius = 1, material = \"red\" }\n";
+        assert_eq!(
+            error(source),
+            "test.toml:8: constant medium boundary material 'red' must be a phase function"
+        );
+        let message = error(&source.replace("density = 1", "density = 0"));
+        assert_eq!(
+            message,
+            "test.toml:8: constant medium density must be positive"
+        );
+        let message = error(&source.replace("\"lambertian\"", "\"isotropic\"").replace(
+            "\"sphere\", center = [0, 0, 0], radius = 1",
+            "\"plane\", point = [0, 0, 0], normal = [0, 1, 0]",
+        ));
+        assert_eq!(
+            message,
+            "test.toml:8: constant medium boundary must be bounded"
+        );
+    }
+
+    #[test]
+    fn parses_coloured_glass() {
+        let scene = load_scene(Path::new("scenes/coloured_glass.toml")).unwrap();
+        // Into the ruby, then across its diameter from the inside.
+        let ray = Ray::new(Point3::new(0.0, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let (attenuation, _) = record.material.scatter(&ray, &record).unwrap();
+        assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+        let inside = Ray::new(record.p, Vec3::new(0.0, 0.0, -1.0));
+        let interior = record.material.interior(&ray, &record, &inside);
+        let record = scene.world.hit(&inside, 0.001, f64::INFINITY).unwrap();
+        let transmittance = interior.current().unwrap().transmittance(record.t);
+        let expected = Color::new((-0.2f64).exp(), (-5.0f64).exp(), (-3.6f64).exp());
+        assert!((transmittance - expected).length() < 1e-9);
+
+        let source = "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                      [materials.glass]\ntype = \"dielectric\"\nrefraction_index = 1.5\n\
+                      transmittance = [0.5, 0.5, 0.5]\n";
+        assert!(parse(source).is_ok());
+        let message = error(&source.replace("0.5]", "0]"));
+        assert_eq!(
+            message,
+            "test.toml:5: material 'glass': transmittance must be above 0 and at most 1"
+        );
+        let message = error(&format!("{}absorption = [1, 1, 1]\n", source));
+        assert_eq!(
+            message,
+            "test.toml:5: material 'glass': absorption and transmittance cannot both be given"
+        );
+    }
+
+    #[test]
+    fn parses_nested_dielectrics() {
+        let scene = load_scene(Path::new("scenes/ice_in_water.toml")).unwrap();
+        // Into the top of the glass ball, then down through the surface of
+        // the water inside it, which the glass hides.
+        let direction = Vec3::new(0.3, -1.0, 0.0).normalize();
+        let ray = Ray::new(Point3::new(1.4, 1.8, 0.0) - 3.0 * direction, direction);
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let inside = Ray::new(record.p, direction);
+        let interior = record.material.interior(&ray, &record, &inside);
+        let record = HitRecord {
+            interior: &interior,
+            ..scene.world.hit(&inside, 0.001, f64::INFINITY).unwrap()
+        };
+        assert!((record.p.y() - 1.0).abs() < 1e-9);
+        let (attenuation, scattered) = record.material.scatter(&inside, &record).unwrap();
+        assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+        assert!((*scattered.direction() - direction).length() < 1e-12);
+    }
+
+    #[test]
+    fn shares_named_dielectrics() {
+        // Two overlapping balls of the same water form a single body.
+        let source = "[camera]\nlook_from = [0, 0, 5]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                      [materials.water]\ntype = \"dielectric\"\nrefraction_index = 1.33\n\
+                      [[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\n\
+                      material = \"water\"\n\
+                      [[objects]]\ntype = \"sphere\"\ncenter = [0, 0, -1.5]\nradius = 1\n\
+                      material = \"water\"\n";
+        let scene = parse(source).unwrap();
+        let medium = |ray: &Ray, interior: &Interior| {
+            let record = HitRecord {
+                interior,
+                ..scene.world.hit(ray, 0.001, f64::INFINITY).unwrap()
+            };
+            let inside = Ray::new(record.p, *ray.direction());
+            (record.material.interior(ray, &record, &inside), inside)
+        };
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let (in_first, inside) = medium(&ray, &Interior::OUTSIDE);
+        let (in_both, _) = medium(&inside, &in_first);
+        assert_eq!(
+            in_both.current().unwrap().id,
+            in_first.current().unwrap().id
+        );
+    }
+
+    #[test]
+    fn parses_phase_functions() {
+        let source = "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                      [materials.milk]\ntype = \"double_henyey_greenstein\"\n\
+                      albedo = [1, 1, 1]\nforward = 0.8\nbackward = -0.3\nweight = 0.9\n\
+                      [materials.air]\ntype = \"rayleigh\"\nalbedo = [0.5, 0.7, 1]\n\
+                      [[objects]]\ntype = \"constant_medium\"\ndensity = 1\n\
+                      boundary = { type = \"sphere\", center = [0, 0, 0], radius = 1, material = \"milk\" }\n\
+                      [[objects]]\ntype = \"constant_medium\"\ndensity = 1\n\
+                      boundary = { type = \"sphere\", center = [0, 0, 5], radius = 1, material = \"air\" }\n";
+        let scene = parse(source).unwrap();
+        assert!(scene.world.bounding_box().is_some());
+
+        let message = error(&source.replace("weight = 0.9", "weight = 1.5"));
+        assert_eq!(
+            message,
+            "test.toml:5: material 'milk': weight must be between 0 and 1"
+        );
+        let message = error(&source.replace("forward = 0.8", "forward = -1"));
+        assert_eq!(
+            message,
+            "test.toml:5: material 'milk': forward and backward must be between -1 and 1"
+        );
+    }
+
+    #[test]
+    fn parses_grid_media() {
+        crate::random::seed(1);
+        let scene = load_scene(Path::new("scenes/cloud.toml")).unwrap();
+        // Up through the middle of the cloud, which hides the sky.
+        let ray = Ray::new(Point3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let transmittance = scene.world.transmittance(&ray, 0.001, f64::INFINITY);
+        assert!(transmittance < 0.05, "{}", transmittance);
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((1.5..=5.5).contains(&record.t), "{}", record.t);
+
+        let source = "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                      [materials.haze]\ntype = \"henyey_greenstein\"\nalbedo = [1, 1, 1]\n\
+                      g = 0.5\n\
+                      [[objects]]\ntype = \"grid_medium\"\npath = \"missing.vol\"\n\
+                      material = \"haze\"\n";
+        let message = error(source);
+        assert!(message.starts_with("./missing.vol: "), "{}", message);
+        let message = error(&source.replace("g = 0.5", "g = 1"));
+        assert_eq!(
+            message,
+            "test.toml:5: material 'haze': g must be between -1 and 1"
+        );
+    }
+
+    #[test]
+    fn parses_analytic_lights() {
+        let scene = parse(include_str!("../../scenes/analytic_lights.toml")).unwrap();
+        assert_eq!(scene.lights.len(), 3);
+
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        for light in scene.lights.iter() {
+            let sample = light.sample(&origin, 0.0).unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            assert!(!light.is_hittable());
+        }
+    }
+
+    #[test]
+    fn reports_invalid_lights() {
+        let camera = "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n";
+        let source = format!(
+            "{}[[lights]]\ntype = \"spot\"\nposition = [0, 1, 0]\nlook_at = [0, 0, 0]\n\
+             intensity = [1, 1, 1]\ninner_angle = 30\nouter_angle = 20\n",
+            camera
+        );
+        let message = error(&source);
+        assert!(message.starts_with("test.toml:5: "), "{}", message);
+
+        let source = format!(
+            "{}[[lights]]\ntype = \"directional\"\ndirection = [0, 0, 0]\nirradiance = [1, 1, 1]\n",
+            camera
+        );
+        let message = error(&source);
+        assert!(message.starts_with("test.toml:5: "), "{}", message);
+    }
+
+    #[test]
+    fn loads_environment_image() {
+        let directory = std::env::temp_dir().join("rustracer-environment-test");
+        fs::create_dir_all(&directory).unwrap();
+        let pixels = vec![Color::new(0.5, 0.25, 1.0); 8];
+        let image = crate::image::Image::new(4, 2, pixels);
+        crate::image::save_image(
+            &directory.join("sky.hdr"),
+            &image,
+            crate::image::ImageFormat::Hdr,
+        )
+        .unwrap();
+
+        let source = "
+[camera]
+look_from = [0, 0, 1]
+look_at = [0, 0, 0]
+vertical_fov = 40
+
+[environment]
+type = \"image\"
+path = \"sky.hdr\"
+rotation = 45
+intensity = 2
+";
+        let scene = parse_scene(source, "test.toml", &directory).unwrap();
+        let radiance = scene.environment.radiance(&Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(radiance, Color::new(1.0, 0.5, 2.0));
+
+        let source = source.replace("sky.hdr", "missing.hdr");
+        let error = match parse_scene(&source, "test.toml", &directory) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error,
+        };
+        assert!(matches!(error, SceneError::Image(_)));
+    }
+
+    #[test]
+    fn builds_textures() {
+        let directory = std::env::temp_dir().join("rustracer-texture-test");
+        fs::create_dir_all(&directory).unwrap();
+        let pixels = vec![Color::new(0.25, 0.5, 1.0); 4];
+        let image = crate::image::Image::new(2, 2, pixels);
+        crate::image::save_image(
+            &directory.join("wood.hdr"),
+            &image,
+            crate::image::ImageFormat::Hdr,
+        )
+        .unwrap();
+
+        let source = "
+[camera]
+look_from = [0, 0, 5]
+look_at = [0, 0, 0]
+vertical_fov = 40
+
+[textures.wood]
+type = \"image\"
+path = \"wood.hdr\"
+
+[textures.floor]
+type = \"checker\"
+even = \"wood\"
+odd = [0, 0, 0]
+
+[materials.wood]
+type = \"lambertian\"
+albedo = \"floor\"
+
+[[objects]]
+type = \"sphere\"
+center = [0, 0, 0]
+radius = 0.5
+material = \"wood\"
+";
+        let scene = parse_scene(source, "test.toml", &directory).unwrap();
+        let ray = Ray::new(Point3::new(0.1, 0.1, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let (attenuation, _) = record.material.scatter(&ray, &record).unwrap();
+        assert_eq!(attenuation, Color::new(0.25, 0.5, 1.0));
+
+        let message = match parse_scene(
+            &source.replace("albedo = \"floor\"", "albedo = \"maple\""),
+            "test.toml",
+            &directory,
+        ) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.to_string(),
+        };
+        assert_eq!(
+            message,
+            "test.toml:16: material 'wood': unknown texture 'maple'"
+        );
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [textures.a]\ntype = \"checker\"\neven = \"b\"\nodd = [0, 0, 0]\n\
+             [textures.b]\ntype = \"checker\"\neven = [1, 1, 1]\nodd = \"a\"\n",
+        );
+        assert!(message.ends_with("refers to itself"), "{}", message);
+    }
+
+    #[test]
+    fn decodes_srgb_textures() {
+        let directory = std::env::temp_dir().join("rustracer-png-texture-test");
+        fs::create_dir_all(&directory).unwrap();
+        // Written with a gamma 2 curve, so stored as 0.5 (127 / 255).
+        let image = crate::image::Image::new(1, 1, vec![Color::new(0.25, 0.25, 0.25)]);
+        crate::image::save_image(
+            &directory.join("grey.png"),
+            &image,
+            crate::image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let albedo = |color_space: &str| {
+            let source = format!(
+                "[camera]\nlook_from = [0, 0, 5]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+                 [textures.grey]\ntype = \"image\"\npath = \"grey.png\"\nwrap = \"mirror\"\n\
+                 color_space = \"{}\"\n\
+                 [materials.grey]\ntype = \"lambertian\"\nalbedo = \"grey\"\n\
+                 [[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"grey\"\n",
+                color_space
+            );
+            let scene = parse_scene(&source, "test.toml", &directory).unwrap();
+            let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+            let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            record.material.scatter(&ray, &record).unwrap().0.x()
+        };
+        let stored = 127.0 / 255.0;
+        assert!((albedo("linear") - stored).abs() < 1e-12);
+        let linear = crate::image::srgb_to_linear(stored);
+        assert!((albedo("srgb") - linear).abs() < 1e-12);
+        assert!((linear - 0.212).abs() < 1e-3);
+    }
+
+    #[test]
+    fn parses_procedural_textures() {
+        let scene = parse(include_str!("../../scenes/procedural.toml")).unwrap();
+        // The glass sphere is tinted by turbulence between two colours.
+        let ray = Ray::new(Point3::new(3.3, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let (tint, _) = record.material.scatter(&ray, &record).unwrap();
+        assert!(
+            tint.z() >= 0.9 - 1e-12 && tint.z() <= 0.95 + 1e-12,
+            "{:?}",
+            tint
+        );
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [textures.marble]\ntype = \"marble\"\noctaves = 0\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:5: texture 'marble': octaves must be positive"
+        );
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [textures.wood]\ntype = \"wood\"\nrings = 3\n",
+        );
+        assert!(message.contains("unknown field `rings`"), "{}", message);
+    }
+
+    #[test]
+    fn parses_moving_spheres() {
+        let scene = parse(include_str!("../../scenes/motion_blur.toml")).unwrap();
+        assert_eq!(scene.camera.shutter_close, 1.0);
+        // The blue sphere touches the floor halfway through the exposure.
+        let ray = Ray::new(Point3::new(0.0, 0.7, 5.0), Vec3::new(0.0, 0.0, -1.0)).with_time(0.5);
+        assert!(scene.world.hit(&ray, 0.001, f64::INFINITY).is_some());
+        let ray = ray.with_time(0.0);
+        assert!(scene.world.hit(&ray, 0.001, f64::INFINITY).is_none());
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0, 0]\n\
+             [[objects]]\ntype = \"moving_sphere\"\nradius = 1\nmaterial = \"red\"\n\
+             keyframes = [{ time = 1, center = [0, 0, 0] }, { time = 0, center = [1, 0, 0] }]\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:8: moving sphere keyframe times must increase"
+        );
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             shutter_open = 1\nshutter_close = 0\n",
+        );
+        assert_eq!(
+            message,
+            "test.toml:1: camera shutter_close must not be before shutter_open"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_material_line() {
+        let source = "
+[camera]
+look_from = [0, 0, 1]
+look_at = [0, 0, 0]
+vertical_fov = 40
+
+[[objects]]
+type = \"sphere\"
+center = [0, 0, 0]
+radius = 1
+material = \"missing\"
+";
+        assert_eq!(error(source), "test.toml:7: unknown material 'missing'");
+    }
+
+    #[test]
+    fn reports_invalid_fields() {
+        let source = "
+[camera]
+look_from = [0, 0, 1]
+look_at = [0, 0, 0]
+vertical_fov = 40
+
+[render]
+samples_per_pixel = 0
+";
+        assert_eq!(
+            error(source),
+            "test.toml:7: render samples_per_pixel must be positive"
+        );
+
+        let message = error("[camera]\nlook_from = [0, 0, 1]\n");
+        assert!(message.contains("missing field `look_at`"), "{}", message);
+
+        let message = error(
+            "[camera]\nlook_from = [0, 0, 1]\nlook_at = [0, 0, 0]\nvertical_fov = 40\n\
+             [materials.red]\ntype = \"lambertian\"\nalbedo = [1, 0]\n",
+        );
+        assert!(message.contains("line 5"), "{}", message);
+    }
+}
@@ -0,0 +1,128 @@
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::objects::{Aabb, VoxelGrid};
+use crate::vec3::*;
+
+const HEADER_SIZE: usize = 48;
+// Value of the encoding field for 32-bit floats, the only one supported.
+const ENCODING_FLOAT32: i32 = 1;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_f32(data: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads a single channel density grid in the binary `.vol` format of
+/// Mitsuba: the bytes `VOL` and the version 3, then little-endian 32-bit
+/// fields for the encoding, the resolution along x, y and z, the number of
+/// channels and the bounds of the grid (min x, y, z then max x, y, z),
+/// followed by the voxels as 32-bit floats with x varying fastest.
+pub fn read_vol(data: &[u8]) -> io::Result<VoxelGrid> {
+    if data.len() < HEADER_SIZE || &data[..3] != b"VOL" {
+        return Err(invalid_data("not a .vol grid file"));
+    }
+    if data[3] != 3 {
+        return Err(invalid_data(format!(
+            "unsupported .vol version {}, expected 3",
+            data[3]
+        )));
+    }
+    if read_i32(data, 4) != ENCODING_FLOAT32 {
+        return Err(invalid_data(".vol grid must hold 32-bit floats"));
+    }
+    let resolution = [read_i32(data, 8), read_i32(data, 12), read_i32(data, 16)];
+    if resolution.iter().any(|&n| n <= 0) {
+        return Err(invalid_data(".vol grid resolution must be positive"));
+    }
+    if read_i32(data, 20) != 1 {
+        return Err(invalid_data(".vol grid must have a single channel"));
+    }
+    let bound = |index: usize| read_f32(data, 24 + 4 * index) as f64;
+    let min = Point3::new(bound(0), bound(1), bound(2));
+    let max = Point3::new(bound(3), bound(4), bound(5));
+    if (0..6).any(|index| !bound(index).is_finite()) {
+        return Err(invalid_data(".vol grid bounds must be finite"));
+    }
+    if (0..3).any(|axis| min[axis] >= max[axis]) {
+        return Err(invalid_data(".vol grid bounds must not be empty"));
+    }
+
+    let resolution = resolution.map(|n| n as usize);
+    let size = resolution
+        .iter()
+        .try_fold(4usize, |size, &n| size.checked_mul(n))
+        .and_then(|size| size.checked_add(HEADER_SIZE));
+    if size != Some(data.len()) {
+        return Err(invalid_data(".vol grid size does not match its resolution"));
+    }
+    let values: Vec<f32> = data[HEADER_SIZE..]
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    if values
+        .iter()
+        .any(|value| !value.is_finite() || *value < 0.0)
+    {
+        return Err(invalid_data(
+            ".vol grid densities must be finite and not negative",
+        ));
+    }
+    Ok(VoxelGrid::new(resolution, values, Aabb::new(min, max)))
+}
+
+pub fn load_vol(path: &Path) -> io::Result<VoxelGrid> {
+    read_vol(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vol(resolution: [i32; 3], values: &[f32]) -> Vec<u8> {
+        let mut data = b"VOL\x03".to_vec();
+        let [x, y, z] = resolution;
+        for field in [ENCODING_FLOAT32, x, y, z, 1].iter() {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for bound in [0.0f32, 0.0, 0.0, 2.0, 1.0, 1.0].iter() {
+            data.extend_from_slice(&bound.to_le_bytes());
+        }
+        for value in values {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn reads_grids() {
+        let grid = read_vol(&vol([2, 1, 1], &[1.0, 3.0])).unwrap();
+        assert_eq!(grid.resolution(), [2, 1, 1]);
+        assert_eq!(grid.maximum(), 3.0);
+        assert_eq!(grid.bounds().max, Point3::new(2.0, 1.0, 1.0));
+        assert_eq!(grid.lookup(&Point3::new(1.0, 0.5, 0.5)), 2.0);
+
+        assert!(read_vol(&vol([2, 1, 1], &[1.0])).is_err());
+        assert!(read_vol(&vol([0, 1, 1], &[])).is_err());
+        assert!(read_vol(&vol([2, 1, 1], &[1.0, -3.0])).is_err());
+        let huge = i32::MAX - 1;
+        assert!(read_vol(&vol([huge, huge, huge], &[1.0])).is_err());
+        assert!(read_vol(b"VOX").is_err());
+
+        for bound in [f32::NAN, f32::INFINITY] {
+            let mut data = vol([2, 1, 1], &[1.0, 3.0]);
+            data[24 + 4 * 3..24 + 4 * 4].copy_from_slice(&bound.to_le_bytes());
+            let error = read_vol(&data).err().unwrap();
+            assert_eq!(error.to_string(), ".vol grid bounds must be finite");
+        }
+    }
+}
@@ -1,162 +1,77 @@
-use std::{f64::INFINITY, usize};
-
-use rand::Rng;
+use std::process;
 
 use rayon::prelude::*;
 
-mod camera;
-mod materials;
-mod objects;
-mod ray;
-mod vec3;
-
-use camera::*;
-use materials::*;
-use objects::*;
-use ray::*;
-use vec3::*;
-
-fn write_color(color: &Color, samples_per_pixel: i32) {
-    let mut col = *color / samples_per_pixel as f64;
-    col = Vec3::new(col.x().sqrt(), col.y().sqrt(), col.z().sqrt());
-
-    let ir = (255.99 * col.x()) as u32;
-    let ig = (255.99 * col.y()) as u32;
-    let ib = (255.99 * col.z()) as u32;
-    println!("{} {} {}", ir, ig, ib)
-}
+use rustracer::image::{save_image, Image};
+use rustracer::integrator::ray_color;
+use rustracer::loaders::load_scene;
+use rustracer::random;
+use rustracer::scene::{random_spheres, Scene};
+use rustracer::vec3::*;
 
-fn ray_color(ray: &Ray, objects: &HittableCollection, bounces: i32) -> Color {
-    // If we've exceeded the ray bounce limit, no more light is gathered.
-    if bounces <= 0 {
-        return Color::new(0.0, 0.0, 0.0);
-    }
+mod cli;
 
-    if let Some(record) = objects.hit(ray, 0.001, INFINITY) {
-        if let Some((attenuation, sub_ray)) = record.material.scatter(ray, &record) {
-            return attenuation * ray_color(&sub_ray, objects, bounces - 1);
-        }
-        return Color::new(0.0, 0.0, 0.0);
-    }
+use cli::Cli;
 
-    let unit_dir: Vec3 = ray.direction().normalize();
-    let t = 0.5 * (unit_dir.y() + 1.0);
-    return (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0);
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
 }
 
 fn main() {
-    let mut rng = rand::thread_rng();
-    // Image
-
-    let aspect_ratio = 16.0 / 9.0;
-    let image_width: usize = 1920;
-    let image_height: usize = (image_width as f64 / aspect_ratio) as usize;
-    let samples_per_pixel = 500;
-    let max_bounces = 50;
-
-    // World
-
-    let ground_material = Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-
-    let mut objs = HittableCollection::new();
-    objs.add(Box::new(Sphere {
-        center: Point3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
-        material: ground_material,
-    }));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let x = (a as f64) + 0.9 * rng.gen_range(0.0..1.0);
-            let y = (b as f64) + 0.9 * rng.gen_range(0.0..1.0);
-            let choose_mat: f64 = rng.gen();
-            let center = Point3::new(x, 0.2, y);
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.6 {
-                    // Diffuse
-                    let albedo = Color::random() * Color::random();
-                    objs.add(Box::new(Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Box::new(Lambertian::new(albedo)),
-                    }));
-                } else if choose_mat < 0.95 {
-                    // Metal
-                    let albedo = Color::random_range(0.5, 1.0);
-                    let fuzz = rng.gen_range(0.0..0.5);
-                    objs.add(Box::new(Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Box::new(Metal::new(albedo, fuzz)),
-                    }));
-                } else {
-                    // Glass
-                    objs.add(Box::new(Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Box::new(Dielectric::new(1.5)),
-                    }));
-                }
-            }
-        }
+    let cli = Cli::parse_and_validate();
+
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .unwrap_or_else(|error| fail(error));
     }
 
-    objs.add(Box::new(Sphere {
-        center: Point3::new(0.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Box::new(Dielectric::new(1.5)),
-    }));
-
-    objs.add(Box::new(Sphere {
-        center: Point3::new(-4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Box::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
-    }));
-
-    objs.add(Box::new(Sphere {
-        center: Point3::new(4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.01)),
-    }));
-
-    // Camera
-
-    let look_from = Point3::new(13.0, 2.0, 3.0);
-    let look_at = Point3::new(0.0, 0.0, 0.0);
-
-    let camera = Camera::new(
-        look_from,
-        look_at,
-        Point3::new(0.0, 1.0, 0.0),
-        20.0,
-        aspect_ratio,
-        0.1,
-        10.0,
-    );
+    if let Some(seed) = cli.seed {
+        random::seed(seed);
+    }
 
-    // Render
+    // Scene: either described by a file, or the built-in random spheres.
+
+    let mut scene: Scene = match &cli.scene {
+        Some(path) => load_scene(path).unwrap_or_else(|error| fail(error)),
+        None => random_spheres(),
+    };
+    cli.apply(&mut scene.settings);
+
+    let image_width = scene.settings.width;
+    let image_height = scene.settings.height;
+    let samples_per_pixel = scene.settings.samples_per_pixel;
+    let max_bounces = scene.settings.max_bounces;
+    let camera = scene.camera();
 
-    let mut screen = vec![Vec3::new(0.0, 0.0, 0.0); (image_height * image_width) as usize];
+    // Render
 
-    println!("P3\n{} {}\n255", image_width, image_height);
+    let mut screen = vec![Vec3::new(0.0, 0.0, 0.0); image_height * image_width];
 
     screen
         .par_iter_mut()
         .enumerate()
         .for_each(|(index, pixel)| {
-            let mut rng = rand::thread_rng();
+            if let Some(seed) = cli.seed {
+                random::seed(random::pixel_seed(seed, index));
+            }
             let i = index % image_width;
             let j = image_height - index / image_width;
             for _ in 0..samples_per_pixel {
-                let u = ((i as f64) + rng.gen::<f64>()) / (image_width - 1) as f64;
-                let v = ((j as f64) + rng.gen::<f64>()) / (image_height - 1) as f64;
+                let u = ((i as f64) + random::random_f64()) / (image_width - 1).max(1) as f64;
+                let v = ((j as f64) + random::random_f64()) / (image_height - 1).max(1) as f64;
                 let ray = camera.get_ray(u, v);
-                *pixel += ray_color(&ray, &objs, max_bounces);
+                *pixel += ray_color(ray, &scene, max_bounces);
             }
         });
 
-    for pixel in screen {
-        write_color(&pixel, samples_per_pixel);
+    // Output
+
+    for pixel in screen.iter_mut() {
+        *pixel /= samples_per_pixel as f64;
     }
+    let image = Image::new(image_width, image_height, screen);
+    save_image(&cli.output, &image, cli.image_format()).unwrap_or_else(|error| fail(error));
 }
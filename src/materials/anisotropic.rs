@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use super::phase::PhaseFunction;
+use super::traits::Material;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::textures::{SolidColor, Texture};
+use crate::vec3::Color;
+use crate::vec3::Vec3;
+
+/// Material of a participating medium scattering light according to a
+/// `phase` function, which sets how much of it keeps going forwards. Like
+/// `Isotropic`, `albedo` is the fraction of the light scattered rather than
+/// absorbed at each event.
+#[derive(Clone)]
+pub struct Anisotropic {
+    pub albedo: Arc<dyn Texture>,
+    pub phase: Arc<dyn PhaseFunction>,
+}
+
+impl Anisotropic {
+    pub fn new(albedo: Color, phase: Arc<dyn PhaseFunction>) -> Anisotropic {
+        Anisotropic::textured(Arc::new(SolidColor::new(albedo)), phase)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, phase: Arc<dyn PhaseFunction>) -> Anisotropic {
+        Anisotropic { albedo, phase }
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.albedo.value(&record.texture_point())
+    }
+}
+
+impl Material for Anisotropic {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<(Vec3, Ray)> {
+        let direction = self.phase.sample(&ray.direction().normalize());
+        Some((self.albedo(record), Ray::new(record.p, direction)))
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        self.albedo(record) * self.pdf(ray, record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, _record: &HitRecord, direction: &Vec3) -> f64 {
+        self.phase
+            .evaluate(&ray.direction().normalize(), &direction.normalize())
+    }
+}
@@ -1,18 +1,112 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::interior::{Interior, Medium, MediumId};
 use super::traits::Material;
 use crate::objects::HitRecord;
+use crate::random::random_f64;
 use crate::ray::Ray;
+use crate::textures::{SolidColor, Texture};
 use crate::vec3::Color;
 use crate::vec3::Vec3;
-use rand::Rng;
 
-#[derive(Clone, Copy)]
+/// A clone is the same material: objects using it and its clones form a
+/// single medium where they overlap. The scene loader gives every object
+/// using a named dielectric a clone of it.
+#[derive(Clone)]
 pub struct Dielectric {
+    /// Tells the material apart from the others in `MediumId`s.
+    id: usize,
     pub refraction_index: f64,
+    /// Filters the light reflected or refracted by the surface.
+    pub tint: Arc<dyn Texture>,
+    /// Fraction of the light absorbed per unit of distance travelled inside
+    /// the material, following the Beer-Lambert law. Zero for clear glass;
+    /// coloured glass, liquids and gems absorb some wavelengths more.
+    pub absorption: Color,
+    /// Where dielectrics overlap, the one with the highest priority fills
+    /// the overlap and the surfaces of the others inside it are ignored. A
+    /// liquid in a glass is modelled by making it overlap the walls, with a
+    /// lower priority than the glass; an ice cube in it by giving the ice a
+    /// higher priority than the liquid.
+    pub priority: i32,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f64) -> Dielectric {
-        Dielectric { refraction_index }
+        let tint = SolidColor::new(Color::new(1.0, 1.0, 1.0));
+        Dielectric::tinted(refraction_index, Arc::new(tint))
+    }
+
+    pub fn tinted(refraction_index: f64, tint: Arc<dyn Texture>) -> Dielectric {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Dielectric {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            refraction_index,
+            tint,
+            absorption: Color::new(0.0, 0.0, 0.0),
+            priority: 0,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Dielectric {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Dielectric {
+        self.absorption = absorption;
+        self
+    }
+
+    /// Sets the absorption so that the fraction `transmittance` of the light
+    /// is left after travelling `distance` inside the material, which is
+    /// easier to pick than the coefficients themselves.
+    pub fn with_transmittance(self, transmittance: Color, distance: f64) -> Dielectric {
+        let absorption = |fraction: f64| -fraction.ln() / distance;
+        self.with_absorption(Color::new(
+            absorption(transmittance.x()),
+            absorption(transmittance.y()),
+            absorption(transmittance.z()),
+        ))
+    }
+
+    /// The medium filled by the object hit at `record`.
+    fn medium(&self, record: &HitRecord) -> Medium {
+        Medium {
+            id: MediumId {
+                material: self.id,
+                instance: record.instance,
+            },
+            priority: self.priority,
+            refraction_index: self.refraction_index,
+            absorption: self.absorption,
+        }
+    }
+
+    /// The refraction indices on the side of the surface `ray` arrives from
+    /// and on the other side, or `None` when the surface lies inside a
+    /// dielectric of higher priority, which hides it.
+    fn indices(&self, record: &HitRecord) -> Option<(f64, f64)> {
+        let index = |interior: &Interior| interior.current().map_or(1.0, |m| m.refraction_index);
+        let hidden = |interior: &Interior| {
+            interior
+                .current()
+                .is_some_and(|medium| medium.priority > self.priority)
+        };
+        if record.front_face {
+            let outside = record.interior;
+            if hidden(outside) {
+                return None;
+            }
+            Some((index(outside), self.refraction_index))
+        } else {
+            let outside = record.interior.left(self.medium(record).id);
+            if hidden(&outside) {
+                return None;
+            }
+            Some((self.refraction_index, index(&outside)))
+        }
     }
 
     fn reflectance(&self, cosine: f64, ref_idx: f64) -> f64 {
@@ -25,20 +119,22 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<(Vec3, Ray)> {
-        let refraction_ratio = if record.front_face {
-            1.0 / self.refraction_index
-        } else {
-            self.refraction_index / 1.0
-        };
-
         let direction_normalized = ray.direction().normalize();
+        let (incident_index, transmitted_index) = match self.indices(record) {
+            Some(indices) => indices,
+            // Not a real interface: the light carries on unchanged.
+            None => {
+                let unchanged = Color::new(1.0, 1.0, 1.0);
+                return Some((unchanged, Ray::new(record.p, direction_normalized)));
+            }
+        };
+        let refraction_ratio = incident_index / transmitted_index;
 
         let cos_thetha = -direction_normalized.dot(&record.normal).min(1.0);
         let sin_thetha = (1.0 - cos_thetha * cos_thetha).sqrt();
 
-        let mut rng = rand::thread_rng();
         let final_direction = if refraction_ratio * sin_thetha > 1.0
-            || self.reflectance(cos_thetha, refraction_ratio) > rng.gen()
+            || self.reflectance(cos_thetha, refraction_ratio) > random_f64()
         {
             // Cannot refract
             direction_normalized.reflect(&record.normal)
@@ -47,7 +143,115 @@ impl Material for Dielectric {
             direction_normalized.refract(&record.normal, refraction_ratio)
         };
 
-        let attenuation = Color::new(0.95, 0.95, 0.95);
+        let attenuation = self.tint.value(&record.texture_point());
         Some((attenuation, Ray::new(record.p, final_direction)))
     }
+
+    fn interior(&self, _ray: &Ray, record: &HitRecord, scattered: &Ray) -> Interior {
+        let interior = record.interior;
+        // Reflected light stays on the side it came from.
+        if scattered.direction().dot(&record.geometric_normal) > 0.0 {
+            return *interior;
+        }
+        if record.front_face {
+            interior.entered(self.medium(record))
+        } else {
+            interior.left(self.medium(record).id)
+        }
+    }
+
+    fn is_specular(&self, _record: &HitRecord) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    #[test]
+    fn absorbs_along_the_path_inside() {
+        let glass = Dielectric::new(1.0).with_transmittance(Color::new(0.5, 0.25, 1.0), 2.0);
+        // With a matching index the light always goes straight through, and
+        // the surface itself lets everything through.
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -2.0));
+        let record = HitRecord::new(&ray, ray.at(2.0), Vec3::new(0.0, 0.0, 1.0), 2.0, &glass);
+        let (attenuation, scattered) = glass.scatter(&ray, &record).unwrap();
+        assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+        // Inside, 4 units of glass, twice the reference distance, absorb.
+        let interior = glass.interior(&ray, &record, &scattered);
+        let transmittance = interior.current().unwrap().transmittance(4.0);
+        assert!((transmittance - Color::new(0.25, 0.0625, 1.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn resolves_nested_dielectrics() {
+        let glass = Dielectric::new(1.5).with_priority(1);
+        let water = Dielectric::new(1.33);
+        // Crossing a glass wall into the water overlapping it.
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let facing = Vec3::new(0.0, 0.0, 1.0);
+        let record = HitRecord::new(&ray, ray.at(1.0), facing, 1.0, &glass);
+        assert_eq!(glass.indices(&record), Some((1.0, 1.5)));
+        let scattered = Ray::new(record.p, *ray.direction());
+        let in_glass = glass.interior(&ray, &record, &scattered);
+        assert_eq!(in_glass.current(), Some(&glass.medium(&record)));
+
+        // The surface of the water inside the wall is hidden by the glass.
+        let ray = scattered;
+        let record = HitRecord {
+            interior: &in_glass,
+            ..HitRecord::new(&ray, ray.at(0.1), facing, 0.1, &water)
+        };
+        assert_eq!(water.indices(&record), None);
+        let (attenuation, scattered) = water.scatter(&ray, &record).unwrap();
+        assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(*scattered.direction(), *ray.direction());
+        let in_both = water.interior(&ray, &record, &scattered);
+        assert_eq!(in_both.current(), Some(&glass.medium(&record)));
+
+        // Leaving the glass through its inner side, into the water.
+        let ray = scattered;
+        let record = HitRecord {
+            interior: &in_both,
+            ..HitRecord::new(&ray, ray.at(0.1), -facing, 0.1, &glass)
+        };
+        assert_eq!(glass.indices(&record), Some((1.5, 1.33)));
+        let scattered = Ray::new(record.p, *ray.direction());
+        let in_water = glass.interior(&ray, &record, &scattered);
+        assert_eq!(in_water.current(), Some(&water.medium(&record)));
+        // A reflection keeps the ray in the glass.
+        let reflected = Ray::new(record.p, -*ray.direction());
+        assert_eq!(glass.interior(&ray, &record, &reflected), in_both);
+    }
+
+    #[test]
+    fn separates_instances_of_a_material() {
+        // Two placements of one glass object, the second inside the first.
+        let glass = Dielectric::new(1.5);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let facing = Vec3::new(0.0, 0.0, 1.0);
+        let outer = HitRecord {
+            instance: 1,
+            ..HitRecord::new(&ray, ray.at(1.0), facing, 1.0, &glass)
+        };
+        let in_outer = glass.interior(&ray, &outer, &ray);
+        let inner = HitRecord {
+            instance: 2,
+            interior: &in_outer,
+            ..HitRecord::new(&ray, ray.at(2.0), facing, 2.0, &glass)
+        };
+        let in_both = glass.interior(&ray, &inner, &ray);
+        // Leaving the inner one keeps the ray inside the outer one.
+        let leaving = HitRecord {
+            instance: 2,
+            interior: &in_both,
+            ..HitRecord::new(&ray, ray.at(3.0), -facing, 3.0, &glass)
+        };
+        assert_eq!(glass.indices(&leaving), Some((1.5, 1.5)));
+        let in_outer_again = glass.interior(&ray, &leaving, &ray);
+        assert_eq!(in_outer_again, in_outer);
+        assert_eq!(in_outer.current(), Some(&glass.medium(&outer)));
+    }
 }
@@ -0,0 +1,36 @@
+use super::traits::Material;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vec3::Color;
+use crate::vec3::Vec3;
+
+/// Emits light of a constant colour from the front side of a surface and
+/// does not scatter incoming light.
+#[derive(Clone, Copy)]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _record: &HitRecord) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emit.x() > 0.0 || self.emit.y() > 0.0 || self.emit.z() > 0.0
+    }
+
+    fn emitted(&self, _ray: &Ray, record: &HitRecord) -> Color {
+        if record.front_face {
+            self.emit
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::vec3::Color;
+
+// Deepest nesting of dielectrics tracked; the outermost ones are forgotten
+// beyond it.
+const MAX_NESTED_MEDIA: usize = 4;
+
+/// Tells a dielectric apart from the others. A material placed several
+/// times, as in an OBJ file loaded once and instanced, fills as many
+/// separate media, one for each `Instance` of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MediumId {
+    pub material: usize,
+    pub instance: usize,
+}
+
+/// A dielectric material that light can travel inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    pub id: MediumId,
+    /// Where dielectrics overlap, the one with the highest priority fills
+    /// the overlap.
+    pub priority: i32,
+    pub refraction_index: f64,
+    /// Fraction of the light absorbed per unit of distance.
+    pub absorption: Color,
+}
+
+impl Medium {
+    /// Fraction of the light left after travelling `distance` through the
+    /// medium, following the Beer-Lambert law.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        let a = self.absorption;
+        Color::new(
+            (-a.x() * distance).exp(),
+            (-a.y() * distance).exp(),
+            (-a.z() * distance).exp(),
+        )
+    }
+}
+
+/// The dielectrics a ray travels inside, which may overlap, such as water
+/// filling a glass up to its walls. Only the one with the highest priority
+/// is actually there; surfaces of the others inside it are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Interior {
+    media: [Option<Medium>; MAX_NESTED_MEDIA],
+}
+
+impl Interior {
+    /// Outside of any dielectric.
+    pub const OUTSIDE: Interior = Interior {
+        media: [None; MAX_NESTED_MEDIA],
+    };
+
+    /// The medium the ray is actually in: the one with the highest
+    /// priority, the last entered among equals. `None` outside of any.
+    pub fn current(&self) -> Option<&Medium> {
+        self.media
+            .iter()
+            .flatten()
+            .fold(None, |current: Option<&Medium>, medium| match current {
+                Some(current) if current.priority > medium.priority => Some(current),
+                _ => Some(medium),
+            })
+    }
+
+    /// The interior after going into `medium`. When `MAX_NESTED_MEDIA`
+    /// are already tracked the one entered first is forgotten, as the ray
+    /// is in the new one and likely to leave the inner ones first.
+    pub fn entered(&self, medium: Medium) -> Interior {
+        let mut interior = *self;
+        if interior.media[MAX_NESTED_MEDIA - 1].is_some() {
+            interior.media.rotate_left(1);
+            interior.media[MAX_NESTED_MEDIA - 1] = None;
+        }
+        if let Some(slot) = interior.media.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(medium);
+        }
+        interior
+    }
+
+    /// The interior after coming out of the medium `id`.
+    pub fn left(&self, id: MediumId) -> Interior {
+        let mut interior = *self;
+        let found = self
+            .media
+            .iter()
+            .position(|medium| medium.is_some_and(|medium| medium.id == id));
+        if let Some(index) = found {
+            // Keeps the media in the order they were entered.
+            interior.media[index..].rotate_left(1);
+            interior.media[MAX_NESTED_MEDIA - 1] = None;
+        }
+        interior
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn medium(material: usize) -> Medium {
+        Medium {
+            id: MediumId {
+                material,
+                instance: 0,
+            },
+            priority: 0,
+            refraction_index: 1.0 + material as f64 / 10.0,
+            absorption: Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn forgets_the_outermost_media() {
+        let mut interior = Interior::OUTSIDE;
+        for material in 0..=MAX_NESTED_MEDIA {
+            interior = interior.entered(medium(material));
+        }
+        // The ray is in the innermost medium, and back in the one around it
+        // once it leaves.
+        assert_eq!(interior.current(), Some(&medium(MAX_NESTED_MEDIA)));
+        let outer = interior.left(medium(MAX_NESTED_MEDIA).id);
+        assert_eq!(outer.current(), Some(&medium(MAX_NESTED_MEDIA - 1)));
+        // The first one entered is gone, and leaving it changes nothing.
+        assert_eq!(outer.left(medium(0).id), outer);
+        let mut interior = outer;
+        for material in 1..MAX_NESTED_MEDIA {
+            interior = interior.left(medium(material).id);
+        }
+        assert_eq!(interior, Interior::OUTSIDE);
+    }
+}
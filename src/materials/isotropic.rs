@@ -0,0 +1,47 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::traits::Material;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::textures::{SolidColor, Texture};
+use crate::vec3::Color;
+use crate::vec3::Vec3;
+
+/// Phase function of a participating medium scattering light equally in
+/// every direction, such as fog. `albedo` is the fraction of the light
+/// scattered rather than absorbed at each event.
+#[derive(Clone)]
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Isotropic {
+        Isotropic { albedo }
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.albedo.value(&record.texture_point())
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray: &Ray, record: &HitRecord) -> Option<(Vec3, Ray)> {
+        let direction = Vec3::random_unit_vector();
+        Some((self.albedo(record), Ray::new(record.p, direction)))
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        // There is no surface, hence no cosine.
+        self.albedo(record) * self.pdf(ray, record, direction)
+    }
+
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord, _direction: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
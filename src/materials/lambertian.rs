@@ -1,24 +1,48 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use super::traits::Material;
 use crate::objects::HitRecord;
 use crate::ray::Ray;
+use crate::textures::{SolidColor, Texture};
 use crate::vec3::Color;
 use crate::vec3::Vec3;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Lambertian {
+        Lambertian::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Lambertian {
         Lambertian { albedo }
     }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.albedo.value(&record.texture_point())
+    }
 }
 
 impl Material for Lambertian {
     fn scatter(&self, _ray: &Ray, record: &HitRecord) -> Option<(Vec3, Ray)> {
-        let scatter_dir = record.normal + Vec3::random_in_unit_sphere();
-        // TODO: Check for NaN
-        Some((self.albedo, Ray::new(record.p, scatter_dir)))
+        // Cosine-weighted direction, so the attenuation is just the albedo.
+        let mut scatter_dir = record.normal + Vec3::random_unit_vector();
+        if scatter_dir.near_zero() {
+            scatter_dir = record.normal;
+        }
+        Some((self.albedo(record), Ray::new(record.p, scatter_dir)))
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        self.albedo(record) * self.pdf(ray, record, direction)
+    }
+
+    fn pdf(&self, _ray: &Ray, record: &HitRecord, direction: &Vec3) -> f64 {
+        let cosine = record.normal.dot(&direction.normalize());
+        cosine.max(0.0) / PI
     }
 }
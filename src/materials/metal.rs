@@ -1,30 +1,41 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use super::traits::Material;
 use crate::objects::HitRecord;
 use crate::ray::Ray;
+use crate::textures::{SolidColor, Texture};
 use crate::vec3::Color;
 use crate::vec3::Vec3;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Metal {
-    albedo: Color,
-    fuzziness: f64,
+    albedo: Arc<dyn Texture>,
+    fuzziness: Arc<dyn Texture>,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzziness: f64) -> Metal {
-        if fuzziness > 1.0 {
-            Metal {
-                albedo,
-                fuzziness: 1.0,
-            }
-        } else if fuzziness < 0.0 {
-            Metal {
-                albedo,
-                fuzziness: 0.0,
-            }
-        } else {
-            Metal { albedo, fuzziness }
-        }
+        let fuzziness = Color::new(fuzziness, fuzziness, fuzziness);
+        Metal::textured(
+            Arc::new(SolidColor::new(albedo)),
+            Arc::new(SolidColor::new(fuzziness)),
+        )
+    }
+
+    /// A metal whose fuzziness is the average of the channels of the
+    /// `fuzziness` texture, clamped to [0, 1].
+    pub fn textured(albedo: Arc<dyn Texture>, fuzziness: Arc<dyn Texture>) -> Metal {
+        Metal { albedo, fuzziness }
+    }
+
+    fn albedo(&self, record: &HitRecord) -> Color {
+        self.albedo.value(&record.texture_point())
+    }
+
+    fn fuzziness(&self, record: &HitRecord) -> f64 {
+        let value = self.fuzziness.value(&record.texture_point());
+        ((value.x() + value.y() + value.z()) / 3.0).clamp(0.0, 1.0)
     }
 }
 
@@ -33,13 +44,88 @@ impl Material for Metal {
         let reflected = ray.direction().normalize().reflect(&record.normal);
         let scattered = Ray::new(
             record.p,
-            reflected + self.fuzziness * Vec3::random_unit_vector(),
+            reflected + self.fuzziness(record) * Vec3::random_unit_vector(),
         );
 
         if scattered.direction().dot(&record.normal) < 0.0 {
             return None;
         }
 
-        Some((self.albedo, scattered))
+        Some((self.albedo(record), scattered))
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        if direction.dot(&record.normal) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        // Directions below the surface are absorbed, so the reflected light
+        // is the albedo times the density of the remaining directions.
+        self.albedo(record) * self.pdf(ray, record, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, direction: &Vec3) -> f64 {
+        // `scatter` picks a point uniformly on the sphere of radius
+        // `fuzziness` centred on the tip of the unit reflected vector r. A
+        // direction w crosses that sphere at distances t solving
+        // t² - 2 t (w·r) + 1 - fuzziness² = 0, and each crossing contributes
+        // t² / (4 pi fuzziness² |cos|) with |cos| = sqrt(discriminant) /
+        // fuzziness.
+        let fuzziness = self.fuzziness(record);
+        if fuzziness <= 0.0 {
+            return 0.0;
+        }
+        let reflected = ray.direction().normalize().reflect(&record.normal);
+        let cosine = direction.normalize().dot(&reflected);
+        let c = 1.0 - fuzziness * fuzziness;
+        let discriminant = cosine * cosine - c;
+        if cosine <= 0.0 || discriminant <= 0.0 {
+            return 0.0;
+        }
+        // Sum of t² over both roots, whose sum is 2 (w·r) and product c.
+        let t_squared = 4.0 * cosine * cosine - 2.0 * c;
+        t_squared / (4.0 * PI * fuzziness * discriminant.sqrt())
+    }
+
+    fn is_specular(&self, record: &HitRecord) -> bool {
+        self.fuzziness(record) == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        let material = Metal::new(Color::new(1.0, 1.0, 1.0), 0.0);
+        let record = HitRecord::new(
+            &ray,
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            &material,
+        );
+
+        for &fuzziness in [0.05, 0.3, 1.0].iter() {
+            let metal = Metal::new(Color::new(1.0, 1.0, 1.0), fuzziness);
+            let n = 800;
+            let mut integral = 0.0;
+            for i in 0..n {
+                let theta = PI * (i as f64 + 0.5) / n as f64;
+                for j in 0..2 * n {
+                    let phi = PI * (j as f64 + 0.5) / n as f64;
+                    let direction = Vec3::new(
+                        theta.sin() * phi.cos(),
+                        theta.cos(),
+                        theta.sin() * phi.sin(),
+                    );
+                    let pdf = metal.pdf(&ray, &record, &direction);
+                    integral += pdf * theta.sin() * (PI / n as f64).powi(2);
+                }
+            }
+            assert!((integral - 1.0).abs() < 0.02, "{}: {}", fuzziness, integral);
+        }
     }
 }
@@ -1,9 +1,19 @@
+pub mod anisotropic;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod interior;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod phase;
 pub mod traits;
 
+pub use anisotropic::*;
 pub use dielectric::*;
+pub use diffuse_light::*;
+pub use interior::*;
+pub use isotropic::*;
 pub use lambertian::*;
 pub use metal::*;
+pub use phase::*;
 pub use traits::*;
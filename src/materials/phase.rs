@@ -0,0 +1,163 @@
+use std::f64::consts::PI;
+
+use crate::random::random_f64;
+use crate::sampling::orthonormal_basis;
+use crate::vec3::Vec3;
+
+/// How a participating medium spreads the light it scatters over the
+/// directions around the one it travelled in.
+pub trait PhaseFunction: Sync + Send {
+    /// Density, with respect to solid angle, of light travelling along
+    /// `direction` being scattered along `scattered`. Both are unit vectors.
+    fn evaluate(&self, direction: &Vec3, scattered: &Vec3) -> f64;
+
+    /// Picks a unit vector along which light travelling along the unit
+    /// vector `direction` is scattered, with the density given by
+    /// `evaluate`.
+    fn sample(&self, direction: &Vec3) -> Vec3;
+}
+
+/// The unit vector at an angle with the given cosine from `direction`,
+/// turned by a random angle around it.
+fn around(direction: &Vec3, cosine: f64) -> Vec3 {
+    let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+    let phi = 2.0 * PI * random_f64();
+    let (u, v) = orthonormal_basis(direction);
+    sine * phi.cos() * u + sine * phi.sin() * v + cosine * *direction
+}
+
+fn henyey_greenstein(g: f64, cosine: f64) -> f64 {
+    let denominator = 1.0 + g * g - 2.0 * g * cosine;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// Inverts the cumulative distribution of the cosine of the Henyey-Greenstein
+/// phase function at `xi`.
+fn sample_henyey_greenstein(g: f64, xi: f64) -> f64 {
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * xi;
+    }
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+    ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+/// The Henyey-Greenstein phase function, a single lobe whose asymmetry `g`
+/// is the mean cosine of the scattering angle: positive values scatter
+/// forwards, as haze and milk do, negative ones backwards, and zero equally
+/// in every direction. `g` must lie strictly between -1 and 1.
+#[derive(Clone, Copy, Debug)]
+pub struct HenyeyGreenstein {
+    pub g: f64,
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn evaluate(&self, direction: &Vec3, scattered: &Vec3) -> f64 {
+        henyey_greenstein(self.g, direction.dot(scattered))
+    }
+
+    fn sample(&self, direction: &Vec3) -> Vec3 {
+        around(direction, sample_henyey_greenstein(self.g, random_f64()))
+    }
+}
+
+/// A blend of two Henyey-Greenstein lobes, usually a strong forward one and
+/// a weaker backward one, which a single lobe cannot match. The `forward`
+/// lobe gets the fraction `weight` of the light and the `backward` one the
+/// rest.
+#[derive(Clone, Copy, Debug)]
+pub struct DoubleHenyeyGreenstein {
+    pub forward: f64,
+    pub backward: f64,
+    pub weight: f64,
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+    fn evaluate(&self, direction: &Vec3, scattered: &Vec3) -> f64 {
+        let cosine = direction.dot(scattered);
+        self.weight * henyey_greenstein(self.forward, cosine)
+            + (1.0 - self.weight) * henyey_greenstein(self.backward, cosine)
+    }
+
+    fn sample(&self, direction: &Vec3) -> Vec3 {
+        let g = if random_f64() < self.weight {
+            self.forward
+        } else {
+            self.backward
+        };
+        around(direction, sample_henyey_greenstein(g, random_f64()))
+    }
+}
+
+/// Scattering by particles much smaller than the wavelength, such as the
+/// molecules of the air: symmetric between forward and backward, and
+/// weakest sideways.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn evaluate(&self, direction: &Vec3, scattered: &Vec3) -> f64 {
+        let cosine = direction.dot(scattered);
+        3.0 / (16.0 * PI) * (1.0 + cosine * cosine)
+    }
+
+    fn sample(&self, direction: &Vec3) -> Vec3 {
+        // The cumulative distribution (cos^3 + 3 cos + 4) / 8 is inverted
+        // with Cardano's formula.
+        let a = 4.0 * random_f64() - 2.0;
+        let u = (a + (a * a + 1.0).sqrt()).cbrt();
+        around(direction, (u - 1.0 / u).clamp(-1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_match_densities() {
+        crate::random::seed(1);
+        let phases: Vec<Box<dyn PhaseFunction>> = vec![
+            Box::new(HenyeyGreenstein { g: 0.0 }),
+            Box::new(HenyeyGreenstein { g: 0.85 }),
+            Box::new(HenyeyGreenstein { g: -0.4 }),
+            Box::new(DoubleHenyeyGreenstein {
+                forward: 0.9,
+                backward: -0.5,
+                weight: 0.7,
+            }),
+            Box::new(Rayleigh),
+        ];
+        let direction = Vec3::new(1.0, 2.0, -2.0) / 3.0;
+        let (side, _) = orthonormal_basis(&direction);
+        for (index, phase) in phases.iter().enumerate() {
+            // Integrates the density over the cosine of the scattering angle.
+            let steps = 10000;
+            let (mut total, mut mean, mut forward) = (0.0, 0.0, 0.0);
+            for step in 0..steps {
+                let cosine = -1.0 + (step as f64 + 0.5) * 2.0 / steps as f64;
+                let scattered = cosine * direction + (1.0 - cosine * cosine).sqrt() * side;
+                let mass = phase.evaluate(&direction, &scattered) * 2.0 * PI * 2.0 / steps as f64;
+                total += mass;
+                mean += cosine * mass;
+                if cosine > 0.5 {
+                    forward += mass;
+                }
+            }
+            assert!((total - 1.0).abs() < 1e-3, "{}: {}", index, total);
+
+            let samples = 20000;
+            let (mut sampled_mean, mut sampled_forward) = (0.0, 0.0);
+            for _ in 0..samples {
+                let scattered = phase.sample(&direction);
+                assert!((scattered.length() - 1.0).abs() < 1e-9);
+                let cosine = scattered.dot(&direction);
+                sampled_mean += cosine / samples as f64;
+                if cosine > 0.5 {
+                    sampled_forward += 1.0 / samples as f64;
+                }
+            }
+            assert!((sampled_mean - mean).abs() < 0.02, "{}: {}", index, mean);
+            assert!((sampled_forward - forward).abs() < 0.02, "{}", index);
+        }
+    }
+}
@@ -1,7 +1,45 @@
+use super::interior::Interior;
 use crate::objects::HitRecord;
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::vec3::{Color, Vec3};
 
 pub trait Material: Sync + Send {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)>;
+
+    /// The reflectance for light arriving from `direction` and leaving
+    /// towards the origin of `ray`, multiplied by the cosine of the angle
+    /// between `direction` and the normal. Specular materials return black.
+    fn eval(&self, _ray: &Ray, _rec: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// The density, with respect to solid angle, with which `scatter` picks
+    /// `direction`. Zero for specular materials.
+    fn pdf(&self, _ray: &Ray, _rec: &HitRecord, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Whether the material only scatters into a few sharp directions at the
+    /// hit, in which case sampling light sources directly is pointless.
+    fn is_specular(&self, _rec: &HitRecord) -> bool {
+        false
+    }
+
+    /// Whether `emitted` can be non-zero, making objects with this material
+    /// light sources.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Radiance emitted by the surface towards the origin of `ray`.
+    fn emitted(&self, _ray: &Ray, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// The dielectrics `scattered`, returned by `scatter`, travels inside,
+    /// given those `ray` travelled inside to reach `rec`. Only materials
+    /// that light goes through change them.
+    fn interior(&self, _ray: &Ray, rec: &HitRecord, _scattered: &Ray) -> Interior {
+        *rec.interior
+    }
 }
@@ -0,0 +1,111 @@
+use crate::vec3::*;
+
+/// Axis-aligned bounding box described by its two extreme corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    pub fn include(&self, point: &Point3) -> Aabb {
+        Aabb {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.extent();
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    /// Index of the axis along which the box is the widest.
+    pub fn longest_axis(&self) -> usize {
+        let d = self.extent();
+        if d.x() > d.y() && d.x() > d.z() {
+            0
+        } else if d.y() > d.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test using a precomputed reciprocal of the ray direction, so that
+    /// traversals testing many boxes against the same ray avoid the divisions.
+    pub fn hit(&self, origin: &Point3, inv_dir: &Vec3, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let mut t0 = (self.min[axis] - origin[axis]) * inv_dir[axis];
+            let mut t1 = (self.max[axis] - origin[axis]) * inv_dir[axis];
+            if inv_dir[axis] < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // Written so that a NaN (ray lying exactly on a slab plane) keeps
+            // the current interval instead of discarding the box.
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    fn hits(bbox: &Aabb, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let d = ray.direction();
+        let inv_dir = Vec3::new(1.0 / d.x(), 1.0 / d.y(), 1.0 / d.z());
+        bbox.hit(ray.origin(), &inv_dir, t_min, t_max)
+    }
+
+    #[test]
+    fn ray_through_box() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(hits(&bbox, &ray, 0.0, f64::INFINITY));
+        assert!(!hits(&bbox, &ray, 0.0, 3.0));
+    }
+
+    #[test]
+    fn ray_missing_box() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Point3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!hits(&bbox, &ray, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn surrounding_and_area() {
+        let a = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Point3::new(1.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0));
+        let both = a.surrounding(&b);
+        assert_eq!(both.min, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(both.max, Point3::new(2.0, 1.0, 1.0));
+        assert_eq!(both.surface_area(), 10.0);
+        assert_eq!(both.longest_axis(), 0);
+    }
+}
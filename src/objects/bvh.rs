@@ -0,0 +1,459 @@
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use super::HittableCollection;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+// Number of buckets the centroids are binned into when evaluating the
+// surface area heuristic. More buckets give slightly better trees at a
+// higher construction cost.
+const SAH_BUCKETS: usize = 12;
+// Relative cost of traversing an interior node with respect to intersecting
+// a single primitive.
+const TRAVERSAL_COST: f64 = 0.125;
+const MAX_LEAF_SIZE: usize = 4;
+
+enum BvhNode {
+    Leaf {
+        bbox: Aabb,
+        first: usize,
+        count: usize,
+    },
+    Interior {
+        bbox: Aabb,
+        // The left child is always stored right after its parent.
+        right: usize,
+        axis: usize,
+    },
+}
+
+impl BvhNode {
+    fn bbox(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bbox, .. } => bbox,
+            BvhNode::Interior { bbox, .. } => bbox,
+        }
+    }
+}
+
+struct BuildItem {
+    index: usize,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bbox: Option<Aabb>,
+}
+
+fn union(a: Option<Aabb>, b: &Aabb) -> Option<Aabb> {
+    Some(match a {
+        Some(a) => a.surrounding(b),
+        None => *b,
+    })
+}
+
+/// Bounding volume hierarchy built with a binned surface area heuristic.
+///
+/// Objects without a bounding box (such as infinite planes) cannot be placed
+/// in the tree; they are kept aside and tested against every ray.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    objects: Vec<Box<dyn Hittable>>,
+    unbounded: Vec<Box<dyn Hittable>>,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Bvh {
+        let mut bounded = vec![];
+        let mut unbounded = vec![];
+        for object in objects {
+            match object.bounding_box() {
+                Some(_) => bounded.push(Some(object)),
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut items: Vec<BuildItem> = bounded
+            .iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bbox = object.as_ref().unwrap().bounding_box().unwrap();
+                BuildItem {
+                    index,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+
+        let mut nodes = vec![];
+        if !items.is_empty() {
+            Bvh::build(&mut nodes, &mut items, 0);
+        }
+
+        // Reorder the objects so that every leaf references a contiguous range.
+        let objects = items
+            .iter()
+            .map(|item| bounded[item.index].take().unwrap())
+            .collect();
+
+        Bvh {
+            nodes,
+            objects,
+            unbounded,
+        }
+    }
+
+    /// Recursively builds the subtree for `items`, which start at position
+    /// `first` of the final object ordering, and returns its node index.
+    fn build(nodes: &mut Vec<BvhNode>, items: &mut [BuildItem], first: usize) -> usize {
+        let bbox = items
+            .iter()
+            .skip(1)
+            .fold(items[0].bbox, |acc, item| acc.surrounding(&item.bbox));
+
+        let node_index = nodes.len();
+        let leaf = BvhNode::Leaf {
+            bbox,
+            first,
+            count: items.len(),
+        };
+
+        if items.len() == 1 {
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        let centroid_bounds = items.iter().skip(1).fold(
+            Aabb::new(items[0].centroid, items[0].centroid),
+            |acc, item| acc.include(&item.centroid),
+        );
+        let axis = centroid_bounds.longest_axis();
+        let axis_min = centroid_bounds.min[axis];
+        let axis_extent = centroid_bounds.max[axis] - axis_min;
+
+        if axis_extent <= 0.0 {
+            // All centroids coincide: no split can separate them.
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        let bucket_of = |item: &BuildItem| -> usize {
+            let b = ((item.centroid[axis] - axis_min) / axis_extent * SAH_BUCKETS as f64) as usize;
+            b.min(SAH_BUCKETS - 1)
+        };
+
+        let mut buckets = [Bucket {
+            count: 0,
+            bbox: None,
+        }; SAH_BUCKETS];
+        for item in items.iter() {
+            let bucket = &mut buckets[bucket_of(item)];
+            bucket.count += 1;
+            bucket.bbox = union(bucket.bbox, &item.bbox);
+        }
+
+        // Evaluate the cost of splitting after every bucket by sweeping the
+        // buckets once from each side.
+        let mut right_area = [0.0; SAH_BUCKETS];
+        let mut right_count = [0; SAH_BUCKETS];
+        let mut acc_box = None;
+        let mut acc_count = 0;
+        for i in (1..SAH_BUCKETS).rev() {
+            acc_count += buckets[i].count;
+            if let Some(b) = buckets[i].bbox {
+                acc_box = union(acc_box, &b);
+            }
+            right_count[i - 1] = acc_count;
+            right_area[i - 1] = acc_box.map_or(0.0, |b| b.surface_area());
+        }
+
+        let mut best_cost = f64::INFINITY;
+        let mut best_split = 0;
+        let mut acc_box = None;
+        let mut acc_count = 0;
+        for i in 0..SAH_BUCKETS - 1 {
+            acc_count += buckets[i].count;
+            if let Some(b) = buckets[i].bbox {
+                acc_box = union(acc_box, &b);
+            }
+            if acc_count == 0 || right_count[i] == 0 {
+                continue;
+            }
+            let left_area = acc_box.map_or(0.0, |b| b.surface_area());
+            let cost = left_area * acc_count as f64 + right_area[i] * right_count[i] as f64;
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = i;
+            }
+        }
+
+        let area = bbox.surface_area();
+        let leaf_cost = items.len() as f64;
+        let split_cost = if area > 0.0 {
+            TRAVERSAL_COST + best_cost / area
+        } else {
+            TRAVERSAL_COST
+        };
+
+        if items.len() <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+            nodes.push(leaf);
+            return node_index;
+        }
+
+        let mut mid = partition(items, |item| bucket_of(item) <= best_split);
+        if mid == 0 || mid == items.len() {
+            // The heuristic could not separate the objects (e.g. all of them
+            // fall in the same bucket); fall back to a median split.
+            items.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+            mid = items.len() / 2;
+        }
+
+        nodes.push(BvhNode::Interior {
+            bbox,
+            right: 0,
+            axis,
+        });
+        let (left_items, right_items) = items.split_at_mut(mid);
+        Bvh::build(nodes, left_items, first);
+        let right_index = Bvh::build(nodes, right_items, first + mid);
+        if let BvhNode::Interior { right, .. } = &mut nodes[node_index] {
+            *right = right_index;
+        }
+        node_index
+    }
+}
+
+fn partition<T>(items: &mut [T], predicate: impl Fn(&T) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+impl From<HittableCollection> for Bvh {
+    fn from(collection: HittableCollection) -> Bvh {
+        Bvh::new(collection.into_objects())
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest = t_max;
+        let mut result = None;
+
+        for object in self.unbounded.iter() {
+            if let Some(record) = object.hit(ray, t_min, closest) {
+                closest = record.t;
+                result = Some(record);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return result;
+        }
+
+        let origin = ray.origin();
+        let inv_dir = Vec3::new(
+            1.0 / ray.direction().x(),
+            1.0 / ray.direction().y(),
+            1.0 / ray.direction().z(),
+        );
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bbox().hit(origin, &inv_dir, t_min, closest) {
+                continue;
+            }
+            match *node {
+                BvhNode::Leaf { first, count, .. } => {
+                    for object in self.objects[first..first + count].iter() {
+                        if let Some(record) = object.hit(ray, t_min, closest) {
+                            closest = record.t;
+                            result = Some(record);
+                        }
+                    }
+                }
+                BvhNode::Interior { right, axis, .. } => {
+                    // Visit the child closer to the ray origin first so that
+                    // `closest` shrinks as early as possible.
+                    if inv_dir[axis] < 0.0 {
+                        stack.push(index + 1);
+                        stack.push(right);
+                    } else {
+                        stack.push(right);
+                        stack.push(index + 1);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        if self
+            .unbounded
+            .iter()
+            .any(|object| object.occluded(ray, t_min, t_max))
+        {
+            return true;
+        }
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let origin = ray.origin();
+        let inv_dir = Vec3::new(
+            1.0 / ray.direction().x(),
+            1.0 / ray.direction().y(),
+            1.0 / ray.direction().z(),
+        );
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bbox().hit(origin, &inv_dir, t_min, t_max) {
+                continue;
+            }
+            match *node {
+                BvhNode::Leaf { first, count, .. } => {
+                    if self.objects[first..first + count]
+                        .iter()
+                        .any(|object| object.occluded(ray, t_min, t_max))
+                    {
+                        return true;
+                    }
+                }
+                BvhNode::Interior { right, .. } => {
+                    stack.push(right);
+                    stack.push(index + 1);
+                }
+            }
+        }
+        false
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut transmittance: f64 = self
+            .unbounded
+            .iter()
+            .map(|object| object.transmittance(ray, t_min, t_max))
+            .product();
+        if transmittance == 0.0 || self.nodes.is_empty() {
+            return transmittance;
+        }
+
+        let origin = ray.origin();
+        let inv_dir = Vec3::new(
+            1.0 / ray.direction().x(),
+            1.0 / ray.direction().y(),
+            1.0 / ray.direction().z(),
+        );
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bbox().hit(origin, &inv_dir, t_min, t_max) {
+                continue;
+            }
+            match *node {
+                BvhNode::Leaf { first, count, .. } => {
+                    for object in self.objects[first..first + count].iter() {
+                        transmittance *= object.transmittance(ray, t_min, t_max);
+                        if transmittance == 0.0 {
+                            return 0.0;
+                        }
+                    }
+                }
+                BvhNode::Interior { right, .. } => {
+                    stack.push(right);
+                    stack.push(index + 1);
+                }
+            }
+        }
+        transmittance
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|node| *node.bbox())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+    use crate::objects::Sphere;
+    use crate::random::random_range;
+
+    fn spheres(description: &[(Point3, f64)]) -> HittableCollection {
+        let mut objs = HittableCollection::new();
+        for &(center, radius) in description {
+            objs.add(Box::new(Sphere {
+                center,
+                radius,
+                material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            }));
+        }
+        objs
+    }
+
+    fn random_description(count: usize) -> Vec<(Point3, f64)> {
+        (0..count)
+            .map(|_| (Vec3::random_range(-10.0, 10.0), random_range(0.1, 1.5)))
+            .collect()
+    }
+
+    #[test]
+    fn matches_linear_scan() {
+        let description = random_description(500);
+        let linear = spheres(&description);
+        let bvh = Bvh::from(spheres(&description));
+
+        for _ in 0..2000 {
+            let origin = Vec3::random_range(-15.0, 15.0);
+            let ray = Ray::new(origin, Vec3::random_unit_vector());
+            let expected = linear.hit(&ray, 0.001, f64::INFINITY).map(|r| r.t);
+            let got = bvh.hit(&ray, 0.001, f64::INFINITY).map(|r| r.t);
+            assert_eq!(expected, got);
+
+            let t_max = random_range(0.0, 20.0);
+            assert_eq!(
+                linear.occluded(&ray, 0.001, t_max),
+                bvh.occluded(&ray, 0.001, t_max)
+            );
+        }
+    }
+
+    #[test]
+    fn empty_tree() {
+        let bvh = Bvh::from(HittableCollection::new());
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(bvh.hit(&ray, 0.0, f64::INFINITY).is_none());
+        assert!(bvh.bounding_box().is_none());
+    }
+
+    #[test]
+    fn bounding_box_covers_objects() {
+        let description = random_description(50);
+        let expected = spheres(&description).bounding_box().unwrap();
+        let bvh = Bvh::from(spheres(&description));
+        assert_eq!(bvh.bounding_box(), Some(expected));
+    }
+}
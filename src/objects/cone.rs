@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
+use super::sphere::sphere_uv;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::polynomial::solve_quadratic;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// Intersects `ray` with a truncated cone standing on `base` along the Y
+/// axis, whose radius goes from `bottom_radius` at the base to `top_radius`
+/// `height` above it. With `capped` the ends are closed by disks.
+///
+/// The side is mapped like a sphere, u around the axis and v upwards; the
+/// caps are mapped to the square enclosing them.
+#[allow(clippy::too_many_arguments)]
+pub fn hit_frustum<'a>(
+    base: &Point3,
+    height: f64,
+    bottom_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let o = *ray.origin() - *base;
+    let d = *ray.direction();
+    // The radius changes by `slope` per unit of height.
+    let slope = (top_radius - bottom_radius) / height;
+    let origin_radius = bottom_radius + slope * o.y();
+
+    let a = d.x() * d.x() + d.z() * d.z() - slope * slope * d.y() * d.y();
+    let b = 2.0 * (o.x() * d.x() + o.z() * d.z() - slope * origin_radius * d.y());
+    let c = o.x() * o.x() + o.z() * o.z() - origin_radius * origin_radius;
+
+    let in_range = |t: f64| t_min <= t && t <= t_max;
+    let mut side = None;
+    for t in solve_quadratic(a, b, c) {
+        let y = o.y() + t * d.y();
+        if in_range(t) && (0.0..=height).contains(&y) {
+            side = Some(t);
+            break;
+        }
+    }
+
+    let mut cap = None;
+    if capped && d.y() != 0.0 {
+        for &(y, radius) in [(0.0, bottom_radius), (height, top_radius)].iter() {
+            let t = (y - o.y()) / d.y();
+            let p = o + t * d;
+            let closer = match (side, cap) {
+                (Some(side), _) if side <= t => false,
+                (_, Some((cap, _, _))) if cap <= t => false,
+                _ => true,
+            };
+            if closer && in_range(t) && p.x() * p.x() + p.z() * p.z() <= radius * radius {
+                cap = Some((t, y, radius));
+            }
+        }
+    }
+
+    if let Some((t, y, radius)) = cap {
+        let p = o + t * d;
+        let outward_normal = Vec3::new(0.0, if y > 0.0 { 1.0 } else { -1.0 }, 0.0);
+        let mut record = HitRecord::new(ray, ray.at(t), outward_normal, t, material);
+        let diameter = 2.0 * radius;
+        let (u, v) = (0.5 + p.x() / diameter, 0.5 + p.z() / diameter);
+        record.set_uv(ray, u, v, 1.0 / diameter);
+        return Some(record);
+    }
+
+    let t = side?;
+    let p = o + t * d;
+    let radius = bottom_radius + slope * p.y();
+    let mut outward_normal = Vec3::new(p.x(), -slope * radius, p.z());
+    if outward_normal.near_zero() {
+        // The apex of a full cone.
+        outward_normal = Vec3::new(0.0, slope.signum(), 0.0);
+    }
+    let mut record = HitRecord::new(ray, ray.at(t), outward_normal.normalize(), t, material);
+    let u = sphere_uv(&Vec3::new(p.x(), 0.0, p.z())).0;
+    let slant = height.hypot(top_radius - bottom_radius);
+    let uv_per_length = (1.0 / (2.0 * PI * radius.max(1e-6))).max(1.0 / slant);
+    record.set_uv(ray, u, p.y() / height, uv_per_length);
+    Some(record)
+}
+
+/// A cone standing on `base` along the Y axis, narrowing from
+/// `bottom_radius` to `top_radius` over `height`: a full cone when
+/// `top_radius` is zero, a truncated one otherwise.
+pub struct Cone {
+    pub base: Point3,
+    pub bottom_radius: f64,
+    pub top_radius: f64,
+    pub height: f64,
+    /// Whether the ends are closed by disks.
+    pub capped: bool,
+    pub material: Box<dyn Material>,
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_frustum(
+            &self.base,
+            self.height,
+            self.bottom_radius,
+            self.top_radius,
+            self.capped,
+            self.material.as_ref(),
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = self.bottom_radius.max(self.top_radius);
+        Some(Aabb::new(
+            self.base - Vec3::new(radius, 0.0, radius),
+            self.base + Vec3::new(radius, self.height, radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    fn cone(top_radius: f64, capped: bool) -> Cone {
+        Cone {
+            base: Point3::new(0.0, 1.0, 0.0),
+            bottom_radius: 2.0,
+            top_radius,
+            height: 2.0,
+            capped,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        }
+    }
+
+    #[test]
+    fn hits_sides_and_caps() {
+        let full = cone(0.0, true);
+        // Halfway up the radius is 1.
+        let ray = Ray::new(Point3::new(5.0, 2.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let record = full.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-9);
+        assert!(record.front_face);
+        let expected = Vec3::new(1.0, 1.0, 0.0).normalize();
+        assert!((record.normal - expected).length() < 1e-9);
+        assert!((record.v - 0.5).abs() < 1e-9);
+
+        // The other nappe, above the apex, is not part of the cone.
+        let ray = Ray::new(Point3::new(5.0, 4.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(full.hit(&ray, 0.0, f64::INFINITY).is_none());
+
+        let up = Ray::new(Point3::new(0.5, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let record = full.hit(&up, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert_eq!(record.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert!(record.front_face);
+        let open = cone(0.0, false);
+        let record = open.hit(&up, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 3.5).abs() < 1e-9);
+        assert!(!record.front_face);
+
+        let truncated = cone(1.0, true);
+        let down = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = truncated.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert_eq!(record.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+}
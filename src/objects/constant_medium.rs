@@ -0,0 +1,142 @@
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+
+// Gap between the entry into the boundary and the search for the exit, so
+// that the entry point is not found again.
+const BOUNDARY_EPSILON: f64 = 1e-4;
+
+/// A volume of uniform density filling a convex `boundary`, such as fog or
+/// smoke. Rays travelling through it are scattered by `phase` after an
+/// exponentially distributed distance, so that the fraction of the light
+/// crossing a length `l` unscattered is `exp(-density * l)`.
+///
+/// The boundary only gives the shape of the volume. To see a surface around
+/// it, such as glass filled with smoke, add that surface as an object of
+/// its own.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    density: f64,
+    phase: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase: Box<dyn Material>,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            density,
+            phase,
+        }
+    }
+}
+
+impl ConstantMedium {
+    /// The part of `ray` between `t_min` and `t_max` inside the boundary.
+    fn inside(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        // The entry is searched along the whole line, as it may lie behind
+        // the origin of the ray.
+        let entry = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY)?.t;
+        let exit = self
+            .boundary
+            .hit(ray, entry + BOUNDARY_EPSILON, f64::INFINITY)?
+            .t;
+        let entry = entry.max(t_min);
+        let exit = exit.min(t_max);
+        if entry < exit {
+            Some((entry, exit))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (entry, exit) = self.inside(ray, t_min, t_max)?;
+        let length = ray.direction().length();
+        let distance = -random_f64().ln() / self.density;
+        if distance > (exit - entry) * length {
+            return None;
+        }
+        let t = entry + distance / length;
+        // Media have no surface; the normal faces back along the ray so the
+        // scattering event counts as a front face.
+        Some(HitRecord::new(
+            ray,
+            ray.at(t),
+            -*ray.direction() / length,
+            t,
+            self.phase.as_ref(),
+        ))
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        match self.inside(ray, t_min, t_max) {
+            Some((entry, exit)) => {
+                (-self.density * (exit - entry) * ray.direction().length()).exp()
+            }
+            None => 1.0,
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::{Isotropic, Lambertian};
+    use crate::objects::Sphere;
+    use crate::vec3::*;
+
+    #[test]
+    fn transmits_exponentially() {
+        crate::random::seed(1);
+        let medium = ConstantMedium::new(
+            Box::new(Sphere {
+                center: Point3::new(0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            }),
+            0.5,
+            Box::new(Isotropic::new(Color::new(1.0, 1.0, 1.0))),
+        );
+        // Crossing the whole diameter, and starting from the centre.
+        let through = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0));
+        let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let samples = 20000;
+        let mut through_hits = 0;
+        let mut inside_hits = 0;
+        for _ in 0..samples {
+            if let Some(record) = medium.hit(&through, 0.0, f64::INFINITY) {
+                assert!((2.0..=3.0).contains(&record.t));
+                assert!(record.front_face);
+                through_hits += 1;
+            }
+            if let Some(record) = medium.hit(&inside, 0.0, f64::INFINITY) {
+                assert!(record.t <= 1.0);
+                inside_hits += 1;
+            }
+        }
+        let scattered = |hits: i32| hits as f64 / samples as f64;
+        assert!((scattered(through_hits) - (1.0 - (-1.0f64).exp())).abs() < 0.02);
+        assert!((scattered(inside_hits) - (1.0 - (-0.5f64).exp())).abs() < 0.02);
+
+        assert!(
+            (medium.transmittance(&through, 0.0, f64::INFINITY) - (-1.0f64).exp()).abs() < 1e-9
+        );
+
+        let outside = Ray::new(Point3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(medium.hit(&outside, 0.0, f64::INFINITY).is_none());
+        assert_eq!(medium.transmittance(&outside, 0.0, f64::INFINITY), 1.0);
+    }
+}
@@ -0,0 +1,193 @@
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use super::triangle::uv_per_length;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::sampling::solid_angle_pdf;
+use crate::vec3::*;
+
+/// An axis-aligned box between the corners `min` and `max`; rotated boxes
+/// are made by placing one with an `Instance`. Each face is mapped to the
+/// whole texture, along the two axes the face spans.
+pub struct Cuboid {
+    pub min: Point3,
+    pub max: Point3,
+    pub material: Box<dyn Material>,
+}
+
+impl Cuboid {
+    /// Every component of `min` must be below that of `max`.
+    pub fn new(min: Point3, max: Point3, material: Box<dyn Material>) -> Cuboid {
+        Cuboid { min, max, material }
+    }
+
+    /// Ray parameters at which `ray` enters and leaves the box, along with
+    /// the axes of the faces crossed there.
+    fn slabs(&self, ray: &Ray) -> ((f64, usize), (f64, usize)) {
+        let mut near = (f64::NEG_INFINITY, 0);
+        let mut far = (f64::INFINITY, 0);
+        for axis in 0..3 {
+            let inv_dir = 1.0 / ray.direction()[axis];
+            let mut t0 = (self.min[axis] - ray.origin()[axis]) * inv_dir;
+            let mut t1 = (self.max[axis] - ray.origin()[axis]) * inv_dir;
+            if inv_dir < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > near.0 {
+                near = (t0, axis);
+            }
+            if t1 < far.0 {
+                far = (t1, axis);
+            }
+        }
+        (near, far)
+    }
+
+    /// The faces seen from `origin`, as an axis and the coordinate of the
+    /// face along it, with their total area. From inside every face is seen.
+    fn visible_faces(&self, origin: &Point3) -> (Vec<(usize, f64)>, f64) {
+        let extent = self.max - self.min;
+        let inside =
+            (0..3).all(|axis| self.min[axis] <= origin[axis] && origin[axis] <= self.max[axis]);
+        let mut faces = Vec::with_capacity(6);
+        let mut area = 0.0;
+        for axis in 0..3 {
+            let face_area = extent[(axis + 1) % 3] * extent[(axis + 2) % 3];
+            if inside || origin[axis] < self.min[axis] {
+                faces.push((axis, self.min[axis]));
+                area += face_area;
+            }
+            if inside || origin[axis] > self.max[axis] {
+                faces.push((axis, self.max[axis]));
+                area += face_area;
+            }
+        }
+        (faces, area)
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (near, far) = self.slabs(ray);
+        if near.0 > far.0 {
+            return None;
+        }
+        // Rays starting inside the box hit it on the way out.
+        let (t, axis) = if near.0 >= t_min { near } else { far };
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let mut outward_normal = Vec3::new(0.0, 0.0, 0.0);
+        let center = 0.5 * (self.min[axis] + self.max[axis]);
+        outward_normal[axis] = if p[axis] > center { 1.0 } else { -1.0 };
+        let mut record = HitRecord::new(ray, p, outward_normal, t, self.material.as_ref());
+
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let extent = self.max - self.min;
+        let u = (p[a] - self.min[a]) / extent[a];
+        let v = (p[b] - self.min[b]) / extent[b];
+        record.set_uv(ray, u, v, uv_per_length(1.0, extent[a] * extent[b]));
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        let (faces, area) = self.visible_faces(origin);
+        if area <= 0.0 {
+            return None;
+        }
+        // Picks a visible face in proportion to its area.
+        let extent = self.max - self.min;
+        let mut left = random_f64() * area;
+        let &(axis, coordinate) = faces
+            .iter()
+            .find(|&&(axis, _)| {
+                left -= extent[(axis + 1) % 3] * extent[(axis + 2) % 3];
+                left < 0.0
+            })
+            .unwrap_or(faces.last()?);
+
+        let mut point = self.min;
+        point[axis] = coordinate;
+        for other in [(axis + 1) % 3, (axis + 2) % 3] {
+            point[other] += random_f64() * extent[other];
+        }
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        normal[axis] = 1.0;
+        let pdf = solid_angle_pdf(origin, &point, &normal, 1.0 / area);
+        if pdf > 0.0 && pdf.is_finite() {
+            Some((point, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        let (_, area) = self.visible_faces(origin);
+        if area <= 0.0 {
+            return 0.0;
+        }
+        solid_angle_pdf(origin, &record.p, &record.geometric_normal, 1.0 / area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn hits_faces_from_outside_and_inside() {
+        let cuboid = Cuboid::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 2.0, 4.0),
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let ray = Ray::new(Point3::new(0.5, 1.0, 8.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = cuboid.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 4.0);
+        assert!(record.front_face);
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!((record.u, record.v), (0.5, 0.5));
+
+        let inside = Ray::new(Point3::new(0.5, 1.0, 1.0), Vec3::new(1.0, 0.0, 0.0));
+        let record = cuboid.hit(&inside, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 0.5);
+        assert!(!record.front_face);
+        assert_eq!(record.normal, Vec3::new(-1.0, 0.0, 0.0));
+
+        let miss = Ray::new(Point3::new(2.0, 1.0, 8.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(cuboid.hit(&miss, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn samples_visible_faces_with_matching_pdf() {
+        let cuboid = Cuboid::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 2.0, 4.0),
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        // From outside, beyond the top face and the one at the largest z,
+        // and from inside.
+        for origin in [Point3::new(0.5, 5.0, 8.0), Point3::new(0.5, 1.0, 1.0)] {
+            for _ in 0..100 {
+                let (point, pdf) = cuboid.sample(&origin, 0.0).unwrap();
+                let ray = Ray::new(origin, point - origin);
+                let record = cuboid.hit(&ray, 0.0, f64::INFINITY).unwrap();
+                assert!((record.t - 1.0).abs() < 1e-9);
+                assert!((cuboid.pdf(&origin, &record, 0.0) - pdf).abs() < 1e-9 * pdf);
+            }
+        }
+        // Only faces whose plane the origin lies beyond are seen.
+        let (faces, area) = cuboid.visible_faces(&Point3::new(0.5, 5.0, 8.0));
+        assert_eq!(faces, vec![(1, 2.0), (2, 4.0)]);
+        assert_eq!(area, 4.0 + 2.0);
+    }
+}
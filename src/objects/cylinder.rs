@@ -0,0 +1,74 @@
+use super::aabb::Aabb;
+use super::cone::hit_frustum;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// A cylinder standing on `base` along the Y axis, `height` tall.
+pub struct Cylinder {
+    pub base: Point3,
+    pub radius: f64,
+    pub height: f64,
+    /// Whether the ends are closed by disks; open cylinders make pipes.
+    pub capped: bool,
+    pub material: Box<dyn Material>,
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_frustum(
+            &self.base,
+            self.height,
+            self.radius,
+            self.radius,
+            self.capped,
+            self.material.as_ref(),
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, 0.0, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn hits_open_and_capped_cylinders() {
+        let mut cylinder = Cylinder {
+            base: Point3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            height: 2.0,
+            capped: false,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        };
+        let ray = Ray::new(Point3::new(0.0, 1.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = cylinder.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!((record.u, record.v), (0.25, 0.75));
+
+        // Looking down the open pipe only finds the inside of the wall.
+        let down = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(1.0, -4.0, 0.0));
+        let record = cylinder.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 1.0).abs() < 1e-9);
+        assert!(!record.front_face);
+
+        cylinder.capped = true;
+        let record = cylinder.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 0.75).abs() < 1e-9);
+        assert!(record.front_face);
+        assert_eq!(record.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,123 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
+use super::plane::intersect_plane;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::sampling::{orthonormal_basis, solid_angle_pdf};
+use crate::vec3::*;
+
+/// A flat disk of the given `radius` around `center`, facing `normal`.
+/// Texture coordinates span the square enclosing the disk, so an image
+/// maps onto it as it would onto a square.
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    tangents: (Vec3, Vec3),
+    material: Box<dyn Material>,
+}
+
+impl Disk {
+    /// `normal` must not be zero and `radius` must be positive.
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Box<dyn Material>) -> Disk {
+        let normal = normal.normalize();
+        Disk {
+            center,
+            normal,
+            radius,
+            tangents: orthonormal_basis(&normal),
+            material,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let t = intersect_plane(ray, &self.center, &self.normal, t_min, t_max)?;
+        let p = ray.at(t);
+        let offset = p - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return None;
+        }
+        let mut record = HitRecord::new(ray, p, self.normal, t, self.material.as_ref());
+        let diameter = 2.0 * self.radius;
+        let u = 0.5 + offset.dot(&self.tangents.0) / diameter;
+        let v = 0.5 + offset.dot(&self.tangents.1) / diameter;
+        record.set_uv(ray, u, v, 1.0 / diameter);
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // The disk reaches out along each axis as far as the radius times
+        // the sine of the angle between the axis and the normal; padded so
+        // that axis-aligned disks do not have a flat box.
+        let mut extent = Vec3::new(1e-6, 1e-6, 1e-6);
+        for axis in 0..3 {
+            let cosine = self.normal[axis];
+            extent[axis] += self.radius * (1.0 - cosine * cosine).max(0.0).sqrt();
+        }
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        let r = self.radius * random_f64().sqrt();
+        let phi = 2.0 * PI * random_f64();
+        let point = self.center + r * phi.cos() * self.tangents.0 + r * phi.sin() * self.tangents.1;
+        let pdf = solid_angle_pdf(origin, &point, &self.normal, 1.0 / self.area());
+        if pdf > 0.0 && pdf.is_finite() {
+            Some((point, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        solid_angle_pdf(origin, &record.p, &self.normal, 1.0 / self.area())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn hits_within_radius() {
+        let disk = Disk::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let ray = Ray::new(Point3::new(1.5, 3.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = disk.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert!(record.front_face);
+        let centre = Ray::new(Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = disk.hit(&centre, 0.0, f64::INFINITY).unwrap();
+        assert_eq!((record.u, record.v), (0.5, 0.5));
+
+        let ray = Ray::new(Point3::new(1.5, 3.0, 1.5), Vec3::new(0.0, -1.0, 0.0));
+        assert!(disk.hit(&ray, 0.0, f64::INFINITY).is_none());
+
+        let bbox = disk.bounding_box().unwrap();
+        assert!((bbox.max - Point3::new(2.0, 1.0, 2.0)).length() < 1e-5);
+
+        let origin = Point3::new(0.5, 4.0, 0.0);
+        for _ in 0..100 {
+            let (point, pdf) = disk.sample(&origin, 0.0).unwrap();
+            let record = disk
+                .hit(&Ray::new(origin, point - origin), 0.0, 1.0 + 1e-9)
+                .unwrap();
+            assert!((disk.pdf(&origin, &record, 0.0) - pdf).abs() < 1e-9 * pdf);
+        }
+    }
+}
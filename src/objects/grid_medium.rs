@@ -0,0 +1,246 @@
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// Densities sampled on a regular grid of voxels filling `bounds`, stored
+/// with x varying fastest, then y, then z. None of them may be negative.
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+    bounds: Aabb,
+    maximum: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(resolution: [usize; 3], values: Vec<f32>, bounds: Aabb) -> VoxelGrid {
+        assert_eq!(
+            values.len(),
+            resolution.iter().product::<usize>(),
+            "grid size does not match its resolution"
+        );
+        let maximum = values.iter().fold(0.0f32, |maximum, &v| maximum.max(v)) as f64;
+        VoxelGrid {
+            resolution,
+            values,
+            bounds,
+            maximum,
+        }
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    /// The largest value of the grid, bounding every lookup.
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x] as f64
+    }
+
+    /// The density at `p`, interpolated trilinearly between the centres of
+    /// the surrounding voxels. Zero outside the bounds.
+    pub fn lookup(&self, p: &Point3) -> f64 {
+        let extent = self.bounds.extent();
+        let mut cells = [(0, 0, 0.0); 3];
+        for (axis, cell) in cells.iter_mut().enumerate() {
+            let relative = (p[axis] - self.bounds.min[axis]) / extent[axis];
+            if !(0.0..=1.0).contains(&relative) {
+                return 0.0;
+            }
+            let n = self.resolution[axis];
+            let x = (relative * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let low = (x as usize).min(n - 1);
+            *cell = (low, (low + 1).min(n - 1), x - low as f64);
+        }
+
+        let [(x0, x1, fx), (y0, y1, fy), (z0, z1, fz)] = cells;
+        let lerp = |a: f64, b: f64, f: f64| a + (b - a) * f;
+        let along_x = |y, z| lerp(self.value(x0, y, z), self.value(x1, y, z), fx);
+        lerp(
+            lerp(along_x(y0, z0), along_x(y1, z0), fy),
+            lerp(along_x(y0, z1), along_x(y1, z1), fy),
+            fz,
+        )
+    }
+}
+
+/// A participating medium whose density varies through its box, such as a
+/// cloud or an explosion: the values of `grid` scaled by `density`.
+///
+/// Scattering distances are sampled by delta tracking and shadow rays are
+/// attenuated by ratio tracking, both against the largest density of the
+/// grid, so the estimates are unbiased however the density varies.
+pub struct GridMedium {
+    grid: Arc<VoxelGrid>,
+    density: f64,
+    phase: Box<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(grid: Arc<VoxelGrid>, density: f64, phase: Box<dyn Material>) -> GridMedium {
+        GridMedium {
+            grid,
+            density,
+            phase,
+        }
+    }
+
+    /// The part of `ray` between `t_min` and `t_max` inside the grid.
+    fn inside(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let bounds = self.grid.bounds();
+        let (mut entry, mut exit) = (t_min, t_max);
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction()[axis];
+            let mut t0 = (bounds.min[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1 = (bounds.max[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN from a ray lying in a face leaves the bounds unchanged.
+            entry = if t0 > entry { t0 } else { entry };
+            exit = if t1 < exit { t1 } else { exit };
+        }
+        if entry < exit {
+            Some((entry, exit))
+        } else {
+            None
+        }
+    }
+
+    /// Advances `t` by a distance sampled against `majorant`, the density
+    /// bounding the medium.
+    fn step(&self, ray: &Ray, t: f64, majorant: f64) -> f64 {
+        t - (1.0 - random_f64()).ln() / (majorant * ray.direction().length())
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (entry, exit) = self.inside(ray, t_min, t_max)?;
+        let majorant = self.density * self.grid.maximum();
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        // Delta tracking: collisions against the majorant are real with the
+        // probability of the actual density, and null otherwise.
+        let mut t = entry;
+        loop {
+            t = self.step(ray, t, majorant);
+            if t >= exit {
+                return None;
+            }
+            let p = ray.at(t);
+            if random_f64() * majorant < self.density * self.grid.lookup(&p) {
+                let length = ray.direction().length();
+                return Some(HitRecord::new(
+                    ray,
+                    p,
+                    -*ray.direction() / length,
+                    t,
+                    self.phase.as_ref(),
+                ));
+            }
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let (entry, exit) = match self.inside(ray, t_min, t_max) {
+            Some(inside) => inside,
+            None => return 1.0,
+        };
+        let majorant = self.density * self.grid.maximum();
+        if majorant <= 0.0 {
+            return 1.0;
+        }
+
+        // Ratio tracking: every tentative collision keeps the fraction of
+        // the light that a null collision would let through.
+        let mut transmittance = 1.0;
+        let mut t = entry;
+        loop {
+            t = self.step(ray, t, majorant);
+            if t >= exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density * self.grid.lookup(&ray.at(t)) / majorant;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.grid.bounds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Isotropic;
+
+    /// A grid whose density rises linearly from 0 at x = 0 to 2 at x = 1,
+    /// and stays constant beyond.
+    fn ramp() -> GridMedium {
+        let values = (0..8).map(|i| if i % 2 == 0 { 0.0 } else { 2.0 }).collect();
+        // Voxel centres lie at x = 0 and 1.
+        let bounds = Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(1.5, 1.5, 1.5));
+        let grid = VoxelGrid::new([2, 2, 2], values, bounds);
+        GridMedium::new(
+            Arc::new(grid),
+            1.0,
+            Box::new(Isotropic::new(Color::new(1.0, 1.0, 1.0))),
+        )
+    }
+
+    #[test]
+    fn interpolates_voxels() {
+        let medium = ramp();
+        let grid = &medium.grid;
+        assert_eq!(grid.maximum(), 2.0);
+        assert!((grid.lookup(&Point3::new(0.25, 0.7, 0.1)) - 0.5).abs() < 1e-9);
+        assert_eq!(grid.lookup(&Point3::new(-0.5, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.lookup(&Point3::new(1.2, 0.5, 0.5)), 2.0);
+        assert_eq!(grid.lookup(&Point3::new(2.0, 0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn tracks_varying_density() {
+        crate::random::seed(1);
+        let medium = ramp();
+        // Across the ramp from x = 0 to 1 the optical depth is 1; from 1 to
+        // 1.5 the density stays at 2.
+        let ray = Ray::new(Point3::new(-2.0, 0.5, 0.5), Vec3::new(2.0, 0.0, 0.0));
+        let expected = (-1.0f64).exp();
+        let samples = 20000;
+        let mut unscattered = 0;
+        let mut transmittance = 0.0;
+        for _ in 0..samples {
+            if medium.hit(&ray, 1.0, 1.5).is_none() {
+                unscattered += 1;
+            }
+            transmittance += medium.transmittance(&ray, 1.0, 1.5) / samples as f64;
+        }
+        let unscattered = unscattered as f64 / samples as f64;
+        assert!((unscattered - expected).abs() < 0.02, "{}", unscattered);
+        assert!((transmittance - expected).abs() < 0.02, "{}", transmittance);
+
+        if let Some(record) = medium.hit(&ray, 0.0, f64::INFINITY) {
+            assert!((1.0..=1.75).contains(&record.t));
+        }
+        let miss = Ray::new(Point3::new(-2.0, 3.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(medium.transmittance(&miss, 0.0, f64::INFINITY), 1.0);
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::ray::Ray;
+use crate::transform::Transform;
+use crate::vec3::*;
+
+/// An object placed in the scene by a transform. The object itself is
+/// shared, so the same mesh can be instanced many times at the cost of a
+/// single copy. Shapes defined along the Y axis, such as `Cylinder`, `Cone`
+/// and `Torus`, are turned to other orientations this way.
+pub struct Instance {
+    /// Tells the hits on this placement of the object from those on the
+    /// others, see `HitRecord::instance`.
+    id: usize,
+    object: Arc<dyn Hittable>,
+    to_world: Transform,
+    to_object: Transform,
+}
+
+impl Instance {
+    /// `transform` takes points of `object` to the scene and must be
+    /// invertible.
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance {
+        // Zero is left for objects that are not instanced.
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+        Instance {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            object,
+            to_world: transform,
+            to_object: transform.inverse(),
+        }
+    }
+
+    /// The ray in object space. Its direction is not normalized, so
+    /// distances along both rays are the same `t`.
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        let direction = self.to_object.vector(ray.direction());
+        let scale = direction.length() / ray.direction().length();
+        Ray::new(self.to_object.point(ray.origin()), direction)
+            .with_footprint(ray.footprint(0.0) * scale, ray.spread())
+            .with_time(ray.time())
+    }
+
+    /// Identifies a hit on the placement `inner` of an object inside this
+    /// instance: the instance itself when the object is placed directly,
+    /// or else a combination of both, so that each copy made by nested
+    /// instances gets its own id.
+    fn placement(&self, inner: usize) -> usize {
+        if inner == 0 {
+            return self.id;
+        }
+        let mut hasher = DefaultHasher::new();
+        (self.id, inner).hash(&mut hasher);
+        // Zero stays reserved for objects that are not instanced.
+        (hasher.finish() as usize).max(1)
+    }
+
+    /// Ratio of the solid angle density seen from `origin` in the scene to
+    /// the density seen from `object_origin` in object space.
+    fn pdf_scale(
+        &self,
+        origin: &Point3,
+        point: &Point3,
+        object_origin: &Point3,
+        object_point: &Point3,
+    ) -> f64 {
+        let ratio = (*point - *origin).length() / (*object_point - *object_origin).length();
+        ratio.powi(3) / self.to_world.determinant().abs()
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut record = self.object.hit(&self.object_ray(ray), t_min, t_max)?;
+        record.p = self.to_world.point(&record.p);
+        record.normal = self.to_world.normal(&record.normal).normalize();
+        record.geometric_normal = self.to_world.normal(&record.geometric_normal).normalize();
+        record.instance = self.placement(record.instance);
+        Some(record)
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.object.occluded(&self.object_ray(ray), t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.object
+            .transmittance(&self.object_ray(ray), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bbox = self.object.bounding_box()?;
+        Some(self.to_world.bounding_box(&bbox))
+    }
+
+    fn sample(&self, origin: &Point3, time: f64) -> Option<(Point3, f64)> {
+        let object_origin = self.to_object.point(origin);
+        let (object_point, pdf) = self.object.sample(&object_origin, time)?;
+        let point = self.to_world.point(&object_point);
+        let pdf = pdf * self.pdf_scale(origin, &point, &object_origin, &object_point);
+        Some((point, pdf))
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, time: f64) -> f64 {
+        let object_origin = self.to_object.point(origin);
+        let object_record = HitRecord {
+            p: self.to_object.point(&record.p),
+            normal: self.to_object.normal(&record.normal).normalize(),
+            geometric_normal: self.to_object.normal(&record.geometric_normal).normalize(),
+            ..*record
+        };
+        self.object.pdf(&object_origin, &object_record, time)
+            * self.pdf_scale(origin, &record.p, &object_origin, &object_record.p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::{Dielectric, Lambertian};
+    use crate::objects::Sphere;
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        Arc::new(Sphere {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        })
+    }
+
+    #[test]
+    fn hits_transformed_object() {
+        let transform = Transform::scaling(&Vec3::new(2.0, 1.0, 1.0))
+            .then(&Transform::translation(&Vec3::new(0.0, 0.0, -10.0)));
+        let instance = Instance::new(unit_sphere(), transform);
+
+        let ray = Ray::new(Point3::new(-5.0, 0.0, -10.0), Vec3::new(1.0, 0.0, 0.0));
+        let record = instance.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 3.0).abs() < 1e-9);
+        assert!((record.p - Point3::new(-2.0, 0.0, -10.0)).length() < 1e-9);
+        assert!((record.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+
+        // Off the axis the normal is that of the ellipsoid, not the sphere.
+        let ray = Ray::new(Point3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = instance.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        let expected = Vec3::new(0.25, 0.0, 0.75f64.sqrt()).normalize();
+        assert!((record.normal - expected).length() < 1e-9);
+
+        let bbox = instance.bounding_box().unwrap();
+        assert_eq!(bbox.min, Point3::new(-2.0, -1.0, -11.0));
+        assert_eq!(bbox.max, Point3::new(2.0, 1.0, -9.0));
+
+        // Hits tell the placements of a shared object apart.
+        let other = Instance::new(unit_sphere(), transform);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let first = instance.hit(&ray, 0.0, f64::INFINITY).unwrap().instance;
+        let second = other.hit(&ray, 0.0, f64::INFINITY).unwrap().instance;
+        assert!(first != 0 && second != 0 && first != second);
+    }
+
+    #[test]
+    fn tells_nested_copies_apart() {
+        // Two copies of a glass ball inside a single outer instance.
+        let glass: Arc<dyn Hittable> = Arc::new(Sphere {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: Box::new(Dielectric::new(1.5)),
+        });
+        let mut copies = crate::objects::HittableCollection::new();
+        for x in [-2.0, 2.0] {
+            let transform = Transform::translation(&Vec3::new(x, 0.0, 0.0));
+            copies.add(Box::new(Instance::new(glass.clone(), transform)));
+        }
+        let outer = Instance::new(
+            Arc::new(copies),
+            Transform::scaling(&Vec3::new(2.0, 2.0, 2.0)),
+        );
+
+        let hit = |x: f64| {
+            let ray = Ray::new(Point3::new(x, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+            outer.hit(&ray, 0.0, f64::INFINITY).unwrap().instance
+        };
+        let (left, right) = (hit(-4.0), hit(4.0));
+        assert!(left != 0 && right != 0 && left != right);
+        // The same copy is always given the same id.
+        assert_eq!(hit(-4.5), left);
+    }
+
+    #[test]
+    fn samples_with_matching_pdf() {
+        let transform = Transform::scaling(&Vec3::new(3.0, 3.0, 3.0))
+            .then(&Transform::translation(&Vec3::new(0.0, 0.0, -10.0)));
+        let instance = Instance::new(unit_sphere(), transform);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        // A uniform scaling keeps the cone subtended by the sphere.
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - (1.0f64 - 0.09).sqrt());
+
+        for _ in 0..100 {
+            let (point, pdf) = instance.sample(&origin, 0.0).unwrap();
+            assert!((pdf * solid_angle - 1.0).abs() < 1e-9);
+            let ray = Ray::new(origin, point - origin);
+            let record = instance.hit(&ray, 0.0, f64::INFINITY).unwrap();
+            assert!((instance.pdf(&origin, &record, 0.0) - pdf).abs() < 1e-9 * pdf);
+        }
+    }
+}
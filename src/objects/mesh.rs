@@ -0,0 +1,276 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use super::bvh::Bvh;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use super::triangle::{intersect_triangle, sample_triangle, triangle_bounding_box, uv_per_length};
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::sampling::{solid_angle_pdf, Distribution1D};
+use crate::vec3::*;
+
+/// Vertex buffers shared by every triangle of a mesh. Normals and texture
+/// coordinates, when present, are indexed exactly like the positions.
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Box<dyn Material>,
+}
+
+impl MeshData {
+    fn vertices(&self, face: usize) -> [&Point3; 3] {
+        let [i0, i1, i2] = self.indices[face];
+        [
+            &self.positions[i0],
+            &self.positions[i1],
+            &self.positions[i2],
+        ]
+    }
+
+    fn face_normal(&self, face: usize) -> Vec3 {
+        let [p0, p1, p2] = self.vertices(face);
+        (*p1 - *p0).cross(&(*p2 - *p0))
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [p0, p1, p2] = self.mesh.vertices(self.face);
+        let (t, b1, b2) = intersect_triangle(ray, p0, p1, p2, t_min, t_max)?;
+
+        let geometric_normal = self.mesh.face_normal(self.face).normalize();
+        let mut record = HitRecord::new(
+            ray,
+            ray.at(t),
+            geometric_normal,
+            t,
+            self.mesh.material.as_ref(),
+        );
+
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        let b0 = 1.0 - b1 - b2;
+        if let Some(normals) = &self.mesh.normals {
+            let shading_normal = b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2];
+            if shading_normal.length_squared() > 0.0 {
+                record.set_shading_normal(shading_normal.normalize());
+            }
+        }
+        let area = 0.5 * self.mesh.face_normal(self.face).length();
+        let (u, v, uv_area) = match &self.mesh.uvs {
+            Some(uvs) => {
+                let (uv0, uv1, uv2) = (uvs[i0], uvs[i1], uvs[i2]);
+                let uv_area = 0.5
+                    * ((uv1.0 - uv0.0) * (uv2.1 - uv0.1) - (uv2.0 - uv0.0) * (uv1.1 - uv0.1)).abs();
+                (
+                    b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                    b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+                    uv_area,
+                )
+            }
+            None => (b1, b2, 0.5),
+        };
+        record.set_uv(ray, u, v, uv_per_length(uv_area, area));
+
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [p0, p1, p2] = self.mesh.vertices(self.face);
+        Some(triangle_bounding_box(p0, p1, p2))
+    }
+}
+
+/// Why `MeshData` does not describe a mesh.
+#[derive(Debug, PartialEq)]
+pub enum MeshError {
+    /// There are normals, but not one per vertex.
+    NormalCount,
+    /// There are texture coordinates, but not one pair per vertex.
+    UvCount,
+    /// A face refers to a vertex that does not exist.
+    IndexOutOfRange,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MeshError::NormalCount => "one normal per vertex expected",
+            MeshError::UvCount => "one uv per vertex expected",
+            MeshError::IndexOutOfRange => "mesh index out of range",
+        })
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// Indexed triangle mesh with a single material. The triangles are stored
+/// in their own BVH so a mesh can be placed in a scene like any other object.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    triangles: Bvh,
+    /// Chooses faces in proportion to their area when sampling points.
+    faces: Distribution1D,
+    area: f64,
+}
+
+impl TriangleMesh {
+    /// Fails unless the normals and texture coordinates, if any, match the
+    /// positions and every index refers to one of them.
+    pub fn new(data: MeshData) -> Result<TriangleMesh, MeshError> {
+        let vertex_count = data.positions.len();
+        if data
+            .normals
+            .as_ref()
+            .is_some_and(|normals| normals.len() != vertex_count)
+        {
+            return Err(MeshError::NormalCount);
+        }
+        if data
+            .uvs
+            .as_ref()
+            .is_some_and(|uvs| uvs.len() != vertex_count)
+        {
+            return Err(MeshError::UvCount);
+        }
+        if data.indices.iter().flatten().any(|&i| i >= vertex_count) {
+            return Err(MeshError::IndexOutOfRange);
+        }
+
+        let data = Arc::new(data);
+        let triangles = (0..data.indices.len())
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: data.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+
+        let areas: Vec<f64> = (0..data.indices.len())
+            .map(|face| 0.5 * data.face_normal(face).length())
+            .collect();
+        let area = areas.iter().sum();
+
+        Ok(TriangleMesh {
+            data,
+            triangles: Bvh::new(triangles),
+            faces: Distribution1D::new(if areas.is_empty() { vec![0.0] } else { areas }),
+            area,
+        })
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.data
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.triangles.bounding_box()
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.triangles.occluded(ray, t_min, t_max)
+    }
+
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        if self.area <= 0.0 {
+            return None;
+        }
+        let (_, _, face) = self.faces.sample(random_f64());
+        let [p0, p1, p2] = self.data.vertices(face);
+        let point = sample_triangle(p0, p1, p2, random_f64(), random_f64());
+        let normal = self.data.face_normal(face);
+        let pdf = solid_angle_pdf(origin, &point, &normal, 1.0 / self.area);
+        if pdf > 0.0 {
+            Some((point, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        if self.area <= 0.0 {
+            return 0.0;
+        }
+        solid_angle_pdf(origin, &record.p, &record.geometric_normal, 1.0 / self.area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    fn quad(normals: Option<Vec<Vec3>>) -> TriangleMesh {
+        TriangleMesh::new(MeshData {
+            positions: vec![
+                Point3::new(-1.0, -1.0, 0.0),
+                Point3::new(1.0, -1.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(-1.0, 1.0, 0.0),
+            ],
+            normals,
+            uvs: None,
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_inconsistent_data() {
+        let data = |normals: usize, uvs: usize, index: usize| MeshData {
+            positions: vec![Point3::new(0.0, 0.0, 0.0); 3],
+            normals: Some(vec![Vec3::new(0.0, 0.0, 1.0); normals]),
+            uvs: Some(vec![(0.0, 0.0); uvs]),
+            indices: vec![[0, 1, index]],
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        };
+        assert!(TriangleMesh::new(data(3, 3, 2)).is_ok());
+        let error = |data| TriangleMesh::new(data).err().unwrap();
+        assert_eq!(error(data(2, 3, 2)), MeshError::NormalCount);
+        assert_eq!(error(data(3, 4, 2)), MeshError::UvCount);
+        assert_eq!(error(data(3, 3, 3)), MeshError::IndexOutOfRange);
+    }
+
+    #[test]
+    fn hits_both_faces() {
+        let mesh = quad(None);
+        for &(x, y) in &[(0.5, -0.5), (-0.5, 0.5)] {
+            let ray = Ray::new(Point3::new(x, y, 2.0), Vec3::new(0.0, 0.0, -1.0));
+            let record = mesh.hit(&ray, 0.0, f64::INFINITY).unwrap();
+            assert!((record.t - 2.0).abs() < 1e-12);
+            assert!(record.front_face);
+            assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn interpolates_shading_normals() {
+        let tilted = Vec3::new(1.0, 0.0, 1.0).normalize();
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let mesh = quad(Some(vec![up, tilted, tilted, up]));
+
+        // Seen from below, the shading normal must be flipped like the
+        // geometric one.
+        let ray = Ray::new(Point3::new(1.0, 0.0, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let record = mesh.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!(!record.front_face);
+        assert!((record.normal + tilted).length() < 1e-9);
+    }
+}
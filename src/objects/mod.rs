@@ -1,8 +1,38 @@
+mod aabb;
+mod bvh;
+mod cone;
+mod constant_medium;
+mod cuboid;
+mod cylinder;
+mod disk;
+mod grid_medium;
+mod instance;
+mod mesh;
+mod moving_sphere;
+mod plane;
+mod quad;
 mod sphere;
+mod torus;
 mod traits;
+mod triangle;
 
+pub use aabb::*;
+pub use bvh::*;
+pub use cone::*;
+pub use constant_medium::*;
+pub use cuboid::*;
+pub use cylinder::*;
+pub use disk::*;
+pub use grid_medium::*;
+pub use instance::*;
+pub use mesh::*;
+pub use moving_sphere::*;
+pub use plane::*;
+pub use quad::*;
 pub use sphere::*;
+pub use torus::*;
 pub use traits::*;
+pub use triangle::*;
 
 type HittableVec = Vec<Box<dyn Hittable>>;
 pub struct HittableCollection {
@@ -17,13 +47,42 @@ impl HittableCollection {
     pub fn add(&mut self, hittable: Box<dyn Hittable>) {
         self.objects.push(hittable);
     }
+
+    pub fn into_objects(self) -> HittableVec {
+        self.objects
+    }
+}
+
+impl Default for HittableCollection {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Hittable for HittableCollection {
-    fn hit(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         self.objects
             .iter()
             .flat_map(|obj| obj.hit(ray, t_min, t_max))
             .min_by(|x, y| x.t.partial_cmp(&y.t).unwrap())
     }
+
+    fn occluded(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> bool {
+        self.objects
+            .iter()
+            .any(|obj| obj.occluded(ray, t_min, t_max))
+    }
+
+    fn transmittance(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> f64 {
+        self.objects
+            .iter()
+            .map(|obj| obj.transmittance(ray, t_min, t_max))
+            .product()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.objects.iter().map(|obj| obj.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |acc, bbox| Some(acc.surrounding(&bbox?)))
+    }
 }
@@ -0,0 +1,130 @@
+use super::aabb::Aabb;
+use super::sphere::{hit_sphere, sample_sphere, sphere_pdf};
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// A sphere whose centre moves along a path through `keyframes`, pairs of
+/// a time and the position of the centre at that time. The centre moves in
+/// straight lines between keyframes and stays still before the first and
+/// after the last one.
+pub struct MovingSphere {
+    keyframes: Vec<(f64, Point3)>,
+    radius: f64,
+    material: Box<dyn Material>,
+}
+
+impl MovingSphere {
+    /// Panics unless there is at least one keyframe and their times
+    /// increase.
+    pub fn new(
+        keyframes: Vec<(f64, Point3)>,
+        radius: f64,
+        material: Box<dyn Material>,
+    ) -> MovingSphere {
+        assert!(!keyframes.is_empty(), "moving sphere without keyframes");
+        assert!(
+            keyframes.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "keyframe times must increase"
+        );
+        MovingSphere {
+            keyframes,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        let next = self.keyframes.partition_point(|&(t, _)| t <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+        let (t0, c0) = self.keyframes[next - 1];
+        let (t1, c1) = self.keyframes[next];
+        let s = (time - t0) / (t1 - t0);
+        (1.0 - s) * c0 + s * c1
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            &self.center(ray.time()),
+            self.radius,
+            self.material.as_ref(),
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // The sphere only moves in straight lines between keyframes, so the
+        // boxes around it at the keyframes enclose it at every moment.
+        let radius = Vec3::new(self.radius, self.radius, self.radius).abs();
+        self.keyframes
+            .iter()
+            .map(|&(_, center)| Aabb::new(center - radius, center + radius))
+            .reduce(|a, b| a.surrounding(&b))
+    }
+
+    fn sample(&self, origin: &Point3, time: f64) -> Option<(Point3, f64)> {
+        sample_sphere(
+            &self.center(time),
+            self.radius,
+            self.material.as_ref(),
+            origin,
+        )
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, time: f64) -> f64 {
+        sphere_pdf(&self.center(time), self.radius, origin, record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn moves_between_keyframes() {
+        let sphere = MovingSphere::new(
+            vec![
+                (0.0, Point3::new(0.0, 0.0, 0.0)),
+                (1.0, Point3::new(2.0, 0.0, 0.0)),
+                (2.0, Point3::new(2.0, 4.0, 0.0)),
+            ],
+            0.5,
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert_eq!(sphere.center(-1.0), Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(sphere.center(0.5), Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(sphere.center(1.5), Point3::new(2.0, 2.0, 0.0));
+        assert_eq!(sphere.center(3.0), Point3::new(2.0, 4.0, 0.0));
+
+        let bbox = sphere.bounding_box().unwrap();
+        assert_eq!(bbox.min, Point3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bbox.max, Point3::new(2.5, 4.5, 0.5));
+
+        // The same ray hits or misses depending on its time.
+        let ray = Ray::new(Point3::new(1.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(sphere.hit(&ray, 0.0, f64::INFINITY).is_none());
+        let ray = ray.with_time(0.5);
+        let record = sphere.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 4.5).abs() < 1e-12);
+
+        // Samples lie on the sphere where it is at their time.
+        let origin = Point3::new(1.0, 0.0, 5.0);
+        let (point, pdf) = sphere.sample(&origin, 0.5).unwrap();
+        assert!(((point - Point3::new(1.0, 0.0, 0.0)).length() - 0.5).abs() < 1e-9);
+        let ray = Ray::new(origin, point - origin).with_time(0.5);
+        let record = sphere.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((sphere.pdf(&origin, &record, 0.5) - pdf).abs() < 1e-9 * pdf);
+    }
+}
@@ -0,0 +1,99 @@
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::sampling::orthonormal_basis;
+use crate::vec3::*;
+
+// Below this value of the cosine between the ray and the plane normal, the
+// ray is taken to be parallel to the plane.
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+/// Ray parameter at which `ray` crosses the plane through `point` with the
+/// given `normal`, if that lies between `t_min` and `t_max`.
+pub fn intersect_plane(
+    ray: &Ray,
+    point: &Point3,
+    normal: &Vec3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<f64> {
+    let denominator = normal.dot(ray.direction());
+    if denominator.abs() < PARALLEL_EPSILON * ray.direction().length() * normal.length() {
+        return None;
+    }
+    let t = normal.dot(&(*point - *ray.origin())) / denominator;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    Some(t)
+}
+
+/// An infinite plane through `point`, facing `normal`. Its texture
+/// coordinates are distances along two directions within the plane, so
+/// textures repeat every unit.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangents: (Vec3, Vec3),
+    material: Box<dyn Material>,
+}
+
+impl Plane {
+    /// `normal` must not be zero.
+    pub fn new(point: Point3, normal: Vec3, material: Box<dyn Material>) -> Plane {
+        let normal = normal.normalize();
+        Plane {
+            point,
+            normal,
+            tangents: orthonormal_basis(&normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let t = intersect_plane(ray, &self.point, &self.normal, t_min, t_max)?;
+        let p = ray.at(t);
+        let mut record = HitRecord::new(ray, p, self.normal, t, self.material.as_ref());
+        let offset = p - self.point;
+        let (u, v) = (offset.dot(&self.tangents.0), offset.dot(&self.tangents.1));
+        record.set_uv(ray, u, v, 1.0);
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    #[test]
+    fn hits_from_both_sides() {
+        let plane = Plane::new(
+            Point3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let down = Ray::new(Point3::new(3.0, 1.0, 4.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = plane.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert!(record.front_face);
+        assert_eq!(record.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        let up = Ray::new(Point3::new(0.0, -3.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        let record = plane.hit(&up, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert!(!record.front_face);
+        assert_eq!(record.normal, Vec3::new(0.0, -1.0, 0.0));
+
+        let parallel = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(plane.hit(&parallel, 0.0, f64::INFINITY).is_none());
+    }
+}
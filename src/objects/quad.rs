@@ -0,0 +1,122 @@
+use super::aabb::Aabb;
+use super::plane::intersect_plane;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use super::triangle::uv_per_length;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::sampling::solid_angle_pdf;
+use crate::vec3::*;
+
+/// A parallelogram with a corner at `corner` and sides along the edges `u`
+/// and `v`, which are also the directions of its texture coordinates. The
+/// outward normal points along `u × v`.
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// Maps a point of the plane to its coordinates along `u` and `v`.
+    w: Vec3,
+    area: f64,
+    material: Box<dyn Material>,
+}
+
+impl Quad {
+    /// `u` and `v` must not be parallel.
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: Box<dyn Material>) -> Quad {
+        let n = u.cross(&v);
+        Quad {
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: n / n.length_squared(),
+            area: n.length(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let t = intersect_plane(ray, &self.corner, &self.normal, t_min, t_max)?;
+        let p = ray.at(t);
+        let offset = p - self.corner;
+        let alpha = self.w.dot(&offset.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&offset));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        let mut record = HitRecord::new(ray, p, self.normal, t, self.material.as_ref());
+        record.set_uv(ray, alpha, beta, uv_per_length(1.0, self.area));
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // Pad the box so that axis-aligned quads do not produce a box with
+        // zero thickness, which the slab test would miss.
+        let padding = Vec3::new(1e-6, 1e-6, 1e-6);
+        let bbox = Aabb::new(self.corner, self.corner)
+            .include(&(self.corner + self.u))
+            .include(&(self.corner + self.v))
+            .include(&(self.corner + self.u + self.v));
+        Some(Aabb::new(bbox.min - padding, bbox.max + padding))
+    }
+
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        let point = self.corner + random_f64() * self.u + random_f64() * self.v;
+        let pdf = solid_angle_pdf(origin, &point, &self.normal, 1.0 / self.area);
+        if pdf > 0.0 && pdf.is_finite() {
+            Some((point, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        solid_angle_pdf(origin, &record.p, &self.normal, 1.0 / self.area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    fn quad() -> Quad {
+        Quad::new(
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn maps_hits_to_edge_coordinates() {
+        let quad = quad();
+        let ray = Ray::new(Point3::new(2.0, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = quad.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 2.0);
+        assert_eq!((record.u, record.v), (0.75, 0.5));
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // Inside the bounding box, outside the parallelogram.
+        let ray = Ray::new(Point3::new(0.2, 0.8, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(quad.hit(&ray, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn samples_visible_points() {
+        let quad = quad();
+        let origin = Point3::new(0.5, 0.5, 3.0);
+        for _ in 0..100 {
+            let (point, pdf) = quad.sample(&origin, 0.0).unwrap();
+            let ray = Ray::new(origin, point - origin);
+            let record = quad.hit(&ray, 0.0, 1.0 + 1e-9).unwrap();
+            assert!((quad.pdf(&origin, &record, 0.0) - pdf).abs() < 1e-9 * pdf);
+        }
+    }
+}
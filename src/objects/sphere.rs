@@ -1,9 +1,130 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
 use super::traits::HitRecord;
 use super::traits::Hittable;
 use crate::materials::Material;
+use crate::random::random_f64;
 use crate::ray::Ray;
+use crate::sampling::{orthonormal_basis, solid_angle_pdf};
 use crate::vec3::*;
 
+/// Texture coordinates of a point on the unit sphere: u runs around the
+/// vertical axis starting from -x, v from the bottom pole to the top one.
+pub fn sphere_uv(point: &Vec3) -> (f64, f64) {
+    let theta = (-point.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-point.z()).atan2(point.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Intersects `ray` with a sphere; a negative radius turns the normals
+/// inwards, making hollow spheres.
+pub fn hit_sphere<'a>(
+    center: &Point3,
+    radius: f64,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let origin_center = *ray.origin() - *center;
+
+    let a = ray.direction().length_squared();
+    let half_b = origin_center.dot(ray.direction());
+    let c = origin_center.length_squared() - radius * radius;
+
+    let discriminant = half_b * half_b - a * c;
+
+    if discriminant < 0.0 {
+        // The ray doesn't intersect the sphere
+        return None;
+    }
+
+    // There is at least one root: Find the nearest root that lies in
+    // the acceptable range provided by the caller.
+
+    let sqrtd = discriminant.sqrt();
+
+    // Consider the first root (the smaller one)
+    let mut root = (-half_b - sqrtd) / a;
+    if root < t_min || t_max < root {
+        // Consider the other root
+        root = (-half_b + sqrtd) / a;
+        if root < t_min || t_max < root {
+            return None;
+        }
+    }
+
+    let p = ray.at(root);
+    let outward_normal = (p - *center) / radius;
+    let mut record = HitRecord::new(ray, p, outward_normal, root, material);
+    let radius = radius.abs();
+    let (u, v) = sphere_uv(&((p - *center) / radius));
+    // Along the parallels u changes faster than v along the meridians;
+    // the faster rate gives the blurrier, alias-free footprint.
+    let sin_theta = (PI * v).sin().max(1e-6);
+    let uv_per_length = (1.0 / (2.0 * PI * radius * sin_theta)).max(1.0 / (PI * radius));
+    record.set_uv(ray, u, v, uv_per_length);
+    Some(record)
+}
+
+/// Picks a point on a sphere as seen from `origin`, see `Hittable::sample`.
+pub fn sample_sphere(
+    center: &Point3,
+    radius: f64,
+    material: &dyn Material,
+    origin: &Point3,
+) -> Option<(Point3, f64)> {
+    let radius = radius.abs();
+    let offset = *center - *origin;
+    let distance_squared = offset.length_squared();
+
+    if distance_squared <= radius * radius {
+        // From inside, every point is visible: sample the whole surface.
+        let normal = Vec3::random_unit_vector();
+        let point = *center + radius * normal;
+        let area_pdf = 1.0 / (4.0 * PI * radius * radius);
+        let pdf = solid_angle_pdf(origin, &point, &normal, area_pdf);
+        return if pdf > 0.0 { Some((point, pdf)) } else { None };
+    }
+
+    // From outside, sample the cone of directions subtended by the
+    // sphere; 1 - cos(theta_max) is computed without cancellation.
+    let sin2_theta_max = radius * radius / distance_squared;
+    let cos_theta_max = (1.0 - sin2_theta_max).max(0.0).sqrt();
+    let one_minus_cos_theta_max = sin2_theta_max / (1.0 + cos_theta_max);
+
+    let cos_theta = 1.0 - random_f64() * one_minus_cos_theta_max;
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * random_f64();
+    let w = offset / distance_squared.sqrt();
+    let (u, v) = orthonormal_basis(&w);
+    let direction = sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * w;
+
+    let record = hit_sphere(
+        center,
+        radius,
+        material,
+        &Ray::new(*origin, direction),
+        0.0,
+        f64::INFINITY,
+    )?;
+    Some((record.p, 1.0 / (2.0 * PI * one_minus_cos_theta_max)))
+}
+
+/// The density with which `sample_sphere` picks the point of `record`.
+pub fn sphere_pdf(center: &Point3, radius: f64, origin: &Point3, record: &HitRecord) -> f64 {
+    let radius = radius.abs();
+    let distance_squared = (*center - *origin).length_squared();
+    if distance_squared <= radius * radius {
+        let area_pdf = 1.0 / (4.0 * PI * radius * radius);
+        return solid_angle_pdf(origin, &record.p, &record.geometric_normal, area_pdf);
+    }
+    let sin2_theta_max = radius * radius / distance_squared;
+    let cos_theta_max = (1.0 - sin2_theta_max).max(0.0).sqrt();
+    1.0 / (2.0 * PI * sin2_theta_max / (1.0 + cos_theta_max))
+}
+
 pub struct Sphere {
     pub center: Point3,
     pub radius: f64,
@@ -11,37 +132,71 @@ pub struct Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let origin_center = *ray.origin() - self.center;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            &self.center,
+            self.radius,
+            self.material.as_ref(),
+            ray,
+            t_min,
+            t_max,
+        )
+    }
 
-        let a = ray.direction().length_squared();
-        let half_b = origin_center.dot(ray.direction());
-        let c = origin_center.length_squared() - self.radius * self.radius;
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius).abs();
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
 
-        let discriminant = half_b * half_b - a * c;
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        sample_sphere(&self.center, self.radius, self.material.as_ref(), origin)
+    }
 
-        if discriminant < 0.0 {
-            // The ray doesn't intersect the sphere
-            return None;
-        }
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        sphere_pdf(&self.center, self.radius, origin, record)
+    }
+}
 
-        // There is at least one root: Find the nearest root that lies in
-        // the acceptable range provided by the caller.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
 
-        let sqrtd = discriminant.sqrt();
+    #[test]
+    fn samples_visible_points() {
+        let sphere = Sphere {
+            center: Point3::new(0.0, 0.0, -10.0),
+            radius: 2.0,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        };
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        // The solid angle of the cone seen from the origin.
+        let solid_angle = 2.0 * PI * (1.0 - (1.0f64 - 0.04).sqrt());
 
-        // Consider the first root (the smaller one)
-        let mut root = (-half_b - sqrtd) / a;
-        if root < t_min || t_max < root {
-            // Consider the other root
-            root = (-half_b + sqrtd) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
+        for _ in 0..100 {
+            let (point, pdf) = sphere.sample(&origin, 0.0).unwrap();
+            assert!(((point - sphere.center).length() - 2.0).abs() < 1e-9);
+            // Only the half facing the origin can be seen.
+            assert!(point.z() > -10.0);
+            assert!((pdf * solid_angle - 1.0).abs() < 1e-9);
+
+            let ray = Ray::new(origin, point - origin);
+            let record = sphere.hit(&ray, 0.0, f64::INFINITY).unwrap();
+            assert!((sphere.pdf(&origin, &record, 0.0) - pdf).abs() < 1e-9 * pdf);
         }
 
-        let p = ray.at(root);
-        let outward_normal = (p - self.center) / self.radius;
-        Some(HitRecord::new(ray, p, outward_normal, root, &self.material))
+        let (point, _) = sphere.sample(&sphere.center, 0.0).unwrap();
+        assert!(((point - sphere.center).length() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn maps_points_to_uv() {
+        let uv = |x, y, z| sphere_uv(&Vec3::new(x, y, z));
+        assert_eq!(uv(-1.0, 0.0, 0.0), (0.0, 0.5));
+        assert_eq!(uv(0.0, 0.0, 1.0), (0.25, 0.5));
+        assert_eq!(uv(1.0, 0.0, 0.0), (0.5, 0.5));
+        assert_eq!(uv(0.0, 0.0, -1.0), (0.75, 0.5));
+        assert_eq!(uv(0.0, 1.0, 0.0).1, 1.0);
+        assert_eq!(uv(0.0, -1.0, 0.0).1, 0.0);
     }
 }
@@ -0,0 +1,127 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
+use super::sphere::sphere_uv;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::polynomial::solve_quartic;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// A ring around the Y axis through `center`: the surface at distance
+/// `minor_radius` from the circle of radius `major_radius`.
+///
+/// u runs around the axis like on a sphere and v around the tube, starting
+/// from its inner side.
+pub struct Torus {
+    pub center: Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: Box<dyn Material>,
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // The quartic is solved in units of the major radius, from the point
+        // of the ray closest to the centre, which keeps its coefficients
+        // small and the roots precise.
+        let scale = self.major_radius;
+        let length = ray.direction().length();
+        let d = *ray.direction() / length;
+        let o = (*ray.origin() - self.center) / scale;
+        let shift = -o.dot(&d);
+        let o = o + shift * d;
+        let r = self.minor_radius / scale;
+        if o.length_squared() > (1.0 + r) * (1.0 + r) {
+            return None;
+        }
+
+        // |p|^2 + 1 - r^2 = 2 sqrt(x^2 + z^2) squared, with p = o + s d and
+        // o perpendicular to d.
+        let g = o.length_squared() + 1.0 - r * r;
+        let f = o.dot(&d);
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            4.0 * f * f + 2.0 * g - 4.0 * (1.0 - d.y() * d.y()),
+            4.0 * f * g - 8.0 * (f - o.y() * d.y()),
+            g * g - 4.0 * (o.length_squared() - o.y() * o.y()),
+        );
+        let t = roots
+            .into_iter()
+            .map(|s| (s + shift) * scale / length)
+            .find(|&t| t_min <= t && t <= t_max)?;
+
+        let p = ray.at(t);
+        let local = p - self.center;
+        let ring_radius = local.x().hypot(local.z());
+        let ring = Vec3::new(local.x(), 0.0, local.z()) * (self.major_radius / ring_radius);
+        let outward_normal = (local - ring).normalize();
+        let mut record = HitRecord::new(ray, p, outward_normal, t, self.material.as_ref());
+
+        let u = sphere_uv(&local).0;
+        let v = (local.y().atan2(ring_radius - self.major_radius) + PI) / (2.0 * PI);
+        let uv_per_length =
+            (1.0 / (2.0 * PI * ring_radius.max(1e-6))).max(1.0 / (2.0 * PI * self.minor_radius));
+        record.set_uv(ray, u, v, uv_per_length);
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3::new(outer, self.minor_radius, outer);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    fn torus() -> Torus {
+        Torus {
+            center: Point3::new(0.0, 1.0, 0.0),
+            major_radius: 3.0,
+            minor_radius: 1.0,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        }
+    }
+
+    #[test]
+    fn hits_outer_and_inner_walls() {
+        let torus = torus();
+        let ray = Ray::new(Point3::new(10.0, 1.0, 0.0), Vec3::new(-2.0, 0.0, 0.0));
+        let record = torus.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 3.0).abs() < 1e-9);
+        assert!((record.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((record.v - 0.5).abs() < 1e-9);
+
+        // From the hole the nearest wall is the inner one.
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let record = torus.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 2.0).abs() < 1e-9);
+        assert!(record.front_face);
+        assert!((record.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+
+        // Straight down through the hole, and over the top.
+        let down = Ray::new(Point3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus.hit(&down, 0.0, f64::INFINITY).is_none());
+        let down = Ray::new(Point3::new(3.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = torus.hit(&down, 0.0, f64::INFINITY).unwrap();
+        assert!((record.t - 8.0).abs() < 1e-9);
+        let grazing = Ray::new(Point3::new(-10.0, 2.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(torus.hit(&grazing, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn hits_from_far_away() {
+        let torus = torus();
+        let origin = Point3::new(1e4, 3e3, -2e4);
+        let target = Point3::new(4.0, 1.0, 0.0);
+        let ray = Ray::new(origin, target - origin);
+        let record = torus.hit(&ray, 0.0, f64::INFINITY).unwrap();
+        assert!((record.p - target).length() < 1e-6);
+    }
+}
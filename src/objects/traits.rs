@@ -1,23 +1,42 @@
-use crate::materials::Material;
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use crate::materials::{Interior, Material};
 use crate::ray::Ray;
+use crate::textures::TexturePoint;
 use crate::vec3::Point3;
 use crate::vec3::Vec3;
 
 pub struct HitRecord<'a> {
     pub p: Point3,
+    /// The normal used for shading, on the side the ray arrived from.
     pub normal: Vec3,
+    /// The true normal of the surface, on the side the ray arrived from.
+    pub geometric_normal: Vec3,
     pub t: f64,
+    /// Texture coordinates of the hit point.
+    pub u: f64,
+    pub v: f64,
+    /// Width of the ray footprint on the surface, in texture coordinates.
+    pub uv_footprint: f64,
     pub front_face: bool,
-    pub material: &'a Box<dyn Material>,
+    pub material: &'a dyn Material,
+    /// Identifies the `Instance` that placed the object hit, or the chain
+    /// of them for nested instances, zero for objects placed directly in
+    /// the scene.
+    pub instance: usize,
+    /// The dielectrics the ray travelled inside to reach the hit, which the
+    /// integrator fills in; outside of any by default.
+    pub interior: &'a Interior,
 }
 
-impl<'a> HitRecord<'_> {
+impl<'a> HitRecord<'a> {
     pub fn new(
         ray: &Ray,
         p: Point3,
         outward_normal: Vec3,
         t: f64,
-        material: &'a Box<dyn Material>,
+        material: &'a dyn Material,
     ) -> HitRecord<'a> {
         let front_face = ray.direction().dot(&outward_normal) < 0.0;
         let normal = if front_face {
@@ -29,13 +48,114 @@ impl<'a> HitRecord<'_> {
         HitRecord {
             p,
             normal,
+            geometric_normal: normal,
             t,
+            u: 0.0,
+            v: 0.0,
+            uv_footprint: 0.0,
             front_face,
             material,
+            instance: 0,
+            interior: &Interior::OUTSIDE,
         }
     }
+
+    /// Sets the texture coordinates of the hit, along with the footprint of
+    /// `ray` in texture space given the rate at which the coordinates change
+    /// per unit of length across the surface.
+    pub fn set_uv(&mut self, ray: &Ray, u: f64, v: f64, uv_per_length: f64) {
+        let cosine = ray
+            .direction()
+            .normalize()
+            .dot(&self.geometric_normal)
+            .abs();
+        self.u = u;
+        self.v = v;
+        self.uv_footprint = ray.footprint(self.t) / cosine.max(1e-6) * uv_per_length;
+    }
+
+    pub fn texture_point(&self) -> TexturePoint {
+        TexturePoint {
+            u: self.u,
+            v: self.v,
+            p: self.p,
+            footprint: self.uv_footprint,
+        }
+    }
+
+    /// Replaces the normal used for shading (e.g. one interpolated from vertex
+    /// normals), flipping it to the side of the surface the ray arrived from.
+    pub fn set_shading_normal(&mut self, shading_normal: Vec3) {
+        self.normal = if shading_normal.dot(&self.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+    }
 }
 
 pub trait Hittable: Sync + Send {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// Box enclosing the whole object, or `None` if the object is unbounded
+    /// (and therefore cannot be placed inside a BVH node).
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Whether anything blocks `ray` between `t_min` and `t_max`. Unlike
+    /// `hit` this may stop at the first intersection found.
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit(ray, t_min, t_max).is_some()
+    }
+
+    /// Fraction of the light travelling along `ray` between `t_min` and
+    /// `t_max` that gets through, which participating media may estimate
+    /// stochastically. Opaque objects let through all or nothing.
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if self.occluded(ray, t_min, t_max) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Picks a point on the surface as seen from `origin`, where the object
+    /// is at `time`, returning it with its density with respect to solid
+    /// angle at `origin`. `None` for objects that cannot be sampled, which
+    /// therefore cannot act as lights.
+    fn sample(&self, _origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        None
+    }
+
+    /// The density with which `sample`, called from `origin` at `time`,
+    /// picks the point of `record`, a hit on this object.
+    fn pdf(&self, _origin: &Point3, _record: &HitRecord, _time: f64) -> f64 {
+        0.0
+    }
+}
+
+/// Lets an object be shared, e.g. between the scene and a light.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.as_ref().hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.as_ref().bounding_box()
+    }
+
+    fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.as_ref().occluded(ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.as_ref().transmittance(ray, t_min, t_max)
+    }
+
+    fn sample(&self, origin: &Point3, time: f64) -> Option<(Point3, f64)> {
+        self.as_ref().sample(origin, time)
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, time: f64) -> f64 {
+        self.as_ref().pdf(origin, record, time)
+    }
 }
@@ -0,0 +1,161 @@
+use super::aabb::Aabb;
+use super::traits::HitRecord;
+use super::traits::Hittable;
+use crate::materials::Material;
+use crate::random::random_f64;
+use crate::ray::Ray;
+use crate::sampling::solid_angle_pdf;
+use crate::vec3::*;
+
+// Determinants below this value mean the ray is parallel to the triangle.
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+/// Möller–Trumbore ray/triangle intersection.
+///
+/// Returns the ray parameter of the hit together with the barycentric
+/// coordinates `(b1, b2)` of the hit point with respect to `p1` and `p2`
+/// (the weight of `p0` being `1 - b1 - b2`).
+pub fn intersect_triangle(
+    ray: &Ray,
+    p0: &Point3,
+    p1: &Point3,
+    p2: &Point3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = *p1 - *p0;
+    let edge2 = *p2 - *p0;
+
+    let pvec = ray.direction().cross(&edge2);
+    let det = edge1.dot(&pvec);
+    if det.abs() < PARALLEL_EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = *ray.origin() - *p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = tvec.cross(&edge1);
+    let b2 = ray.direction().dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+pub fn triangle_bounding_box(p0: &Point3, p1: &Point3, p2: &Point3) -> Aabb {
+    // Pad the box so that axis-aligned triangles do not produce a box with
+    // zero thickness, which the slab test would miss.
+    let padding = Vec3::new(1e-6, 1e-6, 1e-6);
+    let bbox = Aabb::new(*p0, *p0).include(p1).include(p2);
+    Aabb::new(bbox.min - padding, bbox.max + padding)
+}
+
+/// Average rate at which texture coordinates change across a triangle,
+/// from its area in texture space and in space.
+pub fn uv_per_length(uv_area: f64, area: f64) -> f64 {
+    if area > 0.0 {
+        (uv_area / area).sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// Picks a point uniformly on the triangle from two uniform numbers.
+pub fn sample_triangle(p0: &Point3, p1: &Point3, p2: &Point3, u1: f64, u2: f64) -> Point3 {
+    let su = u1.sqrt();
+    let b0 = 1.0 - su;
+    let b1 = u2 * su;
+    b0 * *p0 + b1 * *p1 + (1.0 - b0 - b1) * *p2
+}
+
+/// A single flat-shaded triangle with its own material.
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    pub material: Box<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, material: Box<dyn Material>) -> Triangle {
+        Triangle {
+            vertices: [p0, p1, p2],
+            material,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [p0, p1, p2] = &self.vertices;
+        let (t, b1, b2) = intersect_triangle(ray, p0, p1, p2, t_min, t_max)?;
+        let outward_normal = (*p1 - *p0).cross(&(*p2 - *p0)).normalize();
+        let mut record = HitRecord::new(ray, ray.at(t), outward_normal, t, self.material.as_ref());
+        // Without texture coordinates of its own, the triangle maps its
+        // vertices to (0, 0), (1, 0) and (0, 1).
+        let area = 0.5 * (*p1 - *p0).cross(&(*p2 - *p0)).length();
+        record.set_uv(ray, b1, b2, uv_per_length(0.5, area));
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [p0, p1, p2] = &self.vertices;
+        Some(triangle_bounding_box(p0, p1, p2))
+    }
+
+    fn sample(&self, origin: &Point3, _time: f64) -> Option<(Point3, f64)> {
+        let [p0, p1, p2] = &self.vertices;
+        let normal = (*p1 - *p0).cross(&(*p2 - *p0));
+        let area = 0.5 * normal.length();
+        let point = sample_triangle(p0, p1, p2, random_f64(), random_f64());
+        let pdf = solid_angle_pdf(origin, &point, &normal, 1.0 / area);
+        if pdf > 0.0 && pdf.is_finite() {
+            Some((point, pdf))
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: &Point3, record: &HitRecord, _time: f64) -> f64 {
+        let [p0, p1, p2] = &self.vertices;
+        let area = 0.5 * (*p1 - *p0).cross(&(*p2 - *p0)).length();
+        solid_angle_pdf(origin, &record.p, &record.geometric_normal, 1.0 / area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_inside_triangle() {
+        let p0 = Point3::new(0.0, 0.0, 0.0);
+        let p1 = Point3::new(1.0, 0.0, 0.0);
+        let p2 = Point3::new(0.0, 1.0, 0.0);
+        let ray = Ray::new(Point3::new(0.25, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let (t, b1, b2) = intersect_triangle(&ray, &p0, &p1, &p2, 0.0, f64::INFINITY).unwrap();
+        assert!((t - 1.0).abs() < 1e-12);
+        assert!((b1 - 0.25).abs() < 1e-12);
+        assert!((b2 - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn miss_outside_triangle() {
+        let p0 = Point3::new(0.0, 0.0, 0.0);
+        let p1 = Point3::new(1.0, 0.0, 0.0);
+        let p2 = Point3::new(0.0, 1.0, 0.0);
+        let ray = Ray::new(Point3::new(0.75, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(intersect_triangle(&ray, &p0, &p1, &p2, 0.0, f64::INFINITY).is_none());
+        let parallel = Ray::new(Point3::new(0.1, 0.1, 1.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(intersect_triangle(&parallel, &p0, &p1, &p2, 0.0, f64::INFINITY).is_none());
+    }
+}
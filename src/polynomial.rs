@@ -0,0 +1,170 @@
+//! Real roots of polynomials up to degree four, as needed to intersect
+//! rays with quadrics and tori. Coefficients are given from the highest
+//! degree down and roots are returned in increasing order.
+
+use std::f64::consts::PI;
+
+// Values this close to zero are treated as zero by the closed forms.
+const EPSILON: f64 = 1e-12;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+    // Degenerate coefficients can make the closed forms produce NaNs.
+    roots.retain(|root| root.is_finite());
+    roots.sort_by(|a, b| a.total_cmp(b));
+    roots
+}
+
+/// Roots of `a x^2 + b x + c`, falling back to the linear equation when
+/// `a` is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    // Avoids the cancellation of -b + sqrt(discriminant) when b is large.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        return vec![0.0];
+    }
+    sorted(vec![q / a, c / q])
+}
+
+/// Roots of `a x^3 + b x^2 + c x + d`, with `a` not zero.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    let (a, b, c) = (b / a, c / a, d / a);
+
+    // Substituting x = y - a/3 gives y^3 + 3 p y + 2 q = 0.
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots.
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + PI / 3.0).cos(),
+            -t * (phi - PI / 3.0).cos(),
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+    sorted(roots.into_iter().map(|y| y - a / 3.0).collect())
+}
+
+/// Roots of `a x^4 + b x^3 + c x^2 + d x + e`, with `a` not zero, by
+/// Ferrari's method. The roots are refined with Newton's method, which
+/// recovers most of the precision the closed form loses.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    let (a, b, c, d) = (b / a, c / a, d / a, e / a);
+
+    // Substituting x = y - a/4 gives y^4 + p y^2 + q y + r = 0.
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + c;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * c / 4.0 + d;
+
+    let mut roots = if is_zero(r) {
+        // y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic(1.0, 0.0, p, q);
+        roots.push(0.0);
+        roots
+    } else {
+        // A root of the resolvent cubic splits the quartic into two
+        // quadratics; the largest one is at least p/2, keeping v real.
+        let resolvent = solve_cubic(1.0, -p / 2.0, -r, r * p / 2.0 - q * q / 8.0);
+        let z = match resolvent.last() {
+            Some(&z) => z,
+            None => return vec![],
+        };
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return vec![];
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return vec![];
+        };
+        let v = if q < 0.0 { -v } else { v };
+        let mut roots = solve_quadratic(1.0, v, z - u);
+        roots.extend(solve_quadratic(1.0, -v, z + u));
+        roots
+    };
+
+    for root in roots.iter_mut() {
+        let mut x = *root - a / 4.0;
+        for _ in 0..2 {
+            let f = (((x + a) * x + b) * x + c) * x + d;
+            let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if df == 0.0 {
+                break;
+            }
+            x -= f / df;
+        }
+        *root = x;
+    }
+    sorted(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{:?}", roots);
+        }
+    }
+
+    #[test]
+    fn solves_low_degrees() {
+        assert_roots(solve_quadratic(2.0, -6.0, 4.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(0.0, 2.0, -1.0), &[0.5]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(solve_cubic(1.0, 0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+        assert_roots(solve_cubic(2.0, 0.0, 0.0, -16.0), &[2.0]);
+    }
+
+    #[test]
+    fn solves_quartics() {
+        // (x - 1)(x - 2)(x + 0.5)(x - 4)
+        assert_roots(
+            solve_quartic(1.0, -6.5, 10.5, -1.0, -4.0),
+            &[-0.5, 1.0, 2.0, 4.0],
+        );
+        // (x^2 + 1)(x - 3)(x + 2)
+        assert_roots(solve_quartic(1.0, -1.0, -5.0, -1.0, -6.0), &[-2.0, 3.0]);
+        assert_roots(solve_quartic(1.0, 0.0, 2.0, 0.0, 1.0), &[]);
+        // Degenerate coefficients give no roots rather than NaNs.
+        assert_roots(solve_quartic(0.0, 1.0, 0.0, 0.0, -1.0), &[]);
+        assert_roots(solve_quartic(1.0, f64::NAN, 0.0, 0.0, -1.0), &[]);
+    }
+}
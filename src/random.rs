@@ -0,0 +1,51 @@
+use std::cell::RefCell;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+// Every rendering thread owns its generator. It starts from entropy and can
+// be reseeded so that renders are reproducible regardless of how rayon
+// distributes the pixels among threads.
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Reseeds the generator of the calling thread.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Derives the seed used for the `index`-th pixel of a render seeded with
+/// `seed`, so that neighbouring pixels get unrelated sequences.
+pub fn pixel_seed(seed: u64, index: usize) -> u64 {
+    seed ^ (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+pub fn with_rng<T>(f: impl FnOnce(&mut SmallRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Uniform random number in `[0, 1)`.
+pub fn random_f64() -> f64 {
+    with_rng(|rng| rng.gen())
+}
+
+/// Uniform random number in `[min, max)`.
+pub fn random_range(min: f64, max: f64) -> f64 {
+    with_rng(|rng| rng.gen_range(min..max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeding_is_reproducible() {
+        seed(42);
+        let first: Vec<f64> = (0..8).map(|_| random_f64()).collect();
+        seed(42);
+        let second: Vec<f64> = (0..8).map(|_| random_f64()).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+    }
+}
@@ -3,16 +3,39 @@ use crate::vec3::*;
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
+    /// The ray stands for a cone of light this wide at its origin, growing
+    /// by `spread` per unit of distance travelled. Both are zero for rays
+    /// standing for a single line.
+    width: f64,
+    spread: f64,
+    /// The instant the ray is travelling at, during which moving objects
+    /// are hit where they are at that time.
+    time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, dir: Vec3) -> Ray {
         Ray {
-            origin: origin,
+            origin,
             direction: dir,
+            width: 0.0,
+            spread: 0.0,
+            time: 0.0,
         }
     }
 
+    pub fn with_time(mut self, time: f64) -> Ray {
+        self.time = time;
+        self
+    }
+
+    /// Gives the ray a footprint, see `footprint`.
+    pub fn with_footprint(mut self, width: f64, spread: f64) -> Ray {
+        self.width = width;
+        self.spread = spread;
+        self
+    }
+
     pub fn origin(&self) -> &Vec3 {
         &self.origin
     }
@@ -21,7 +44,23 @@ impl Ray {
         &self.direction
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn at(&self, t: f64) -> Vec3 {
         self.origin + self.direction * t
     }
+
+    /// Width of the cone of light the ray stands for at `at(t)`, measured
+    /// across the ray. Used to filter textures over the area one sample
+    /// covers.
+    pub fn footprint(&self, t: f64) -> f64 {
+        self.width + self.spread * t * self.direction.length()
+    }
+
+    /// Growth of the footprint per unit of distance along the ray.
+    pub fn spread(&self) -> f64 {
+        self.spread
+    }
 }
@@ -0,0 +1,188 @@
+use crate::vec3::*;
+
+/// A piecewise-constant function over `[0, 1)` that can be sampled
+/// proportionally to its values.
+pub struct Distribution1D {
+    function: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// `function` holds the (non-negative) values of equally sized steps.
+    /// A function that is zero everywhere is sampled uniformly.
+    pub fn new(function: Vec<f64>) -> Distribution1D {
+        assert!(!function.is_empty(), "empty distribution");
+        let n = function.len();
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for (i, value) in function.iter().enumerate() {
+            cdf.push(cdf[i] + value.max(0.0) / n as f64);
+        }
+
+        let integral = cdf[n];
+        if integral > 0.0 {
+            for value in cdf.iter_mut() {
+                *value /= integral;
+            }
+        } else {
+            for (i, value) in cdf.iter_mut().enumerate() {
+                *value = i as f64 / n as f64;
+            }
+        }
+
+        Distribution1D {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.function.len()
+    }
+
+    /// The integral of the function over `[0, 1)`.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform `u` in `[0, 1)` to `x` in `[0, 1)`, returning `x`, its
+    /// density and the index of the step containing it.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // The last step whose cdf is at most u, skipping empty steps.
+        let index = self.cdf.partition_point(|&c| c <= u).clamp(1, self.len()) - 1;
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+        let x = ((index as f64 + offset) / self.len() as f64).min(1.0 - f64::EPSILON);
+        (x, self.pdf(x), index)
+    }
+
+    /// The density with which `sample` returns `x`.
+    pub fn pdf(&self, x: f64) -> f64 {
+        let index = ((x * self.len() as f64) as usize).min(self.len() - 1);
+        if self.integral > 0.0 {
+            self.function[index].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant function over `[0, 1)²`, given as a grid of values
+/// stored row by row, sampled by first choosing a row and then a column.
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(function: &[f64], width: usize, height: usize) -> Distribution2D {
+        assert_eq!(function.len(), width * height, "wrong number of values");
+        let rows: Vec<Distribution1D> = function
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral()).collect());
+        Distribution2D { rows, marginal }
+    }
+
+    /// The integral of the function over `[0, 1)²`.
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    /// Maps uniform `(u1, u2)` to a point `(u, v)`, `u` along the rows and
+    /// `v` across them, returning the point and its density.
+    pub fn sample(&self, u1: f64, u2: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(u2);
+        let (u, pdf_u, _) = self.rows[row].sample(u1);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    /// The density with which `sample` returns `(u, v)`.
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row = ((v * self.rows.len() as f64) as usize).min(self.rows.len() - 1);
+        self.marginal.pdf(v) * self.rows[row].pdf(u)
+    }
+}
+
+/// Two unit vectors forming an orthonormal basis with the unit vector `w`.
+pub fn orthonormal_basis(w: &Vec3) -> (Vec3, Vec3) {
+    // Duff et al., "Building an Orthonormal Basis, Revisited".
+    let sign = 1f64.copysign(w.z());
+    let a = -1.0 / (sign + w.z());
+    let b = w.x() * w.y() * a;
+    (
+        Vec3::new(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x()),
+        Vec3::new(b, sign + w.y() * w.y() * a, -w.y()),
+    )
+}
+
+/// Converts the density `area_pdf` of `point`, on a surface with the given
+/// `normal`, into a density with respect to solid angle at `origin`.
+pub fn solid_angle_pdf(origin: &Point3, point: &Point3, normal: &Vec3, area_pdf: f64) -> f64 {
+    let offset = *point - *origin;
+    let distance_squared = offset.length_squared();
+    let cosine = normal.dot(&offset).abs() / (distance_squared.sqrt() * normal.length());
+    if cosine <= 0.0 {
+        return 0.0;
+    }
+    area_pdf * distance_squared / cosine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_proportionally() {
+        let distribution = Distribution1D::new(vec![0.0, 3.0, 1.0, 0.0]);
+        assert_eq!(distribution.integral(), 1.0);
+
+        let (x, pdf, index) = distribution.sample(0.5);
+        assert_eq!((x, pdf, index), (0.25 + 0.25 * 2.0 / 3.0, 3.0, 1));
+        let (x, pdf, index) = distribution.sample(0.9);
+        assert!((x - (0.5 + 0.25 * 0.6)).abs() < 1e-12);
+        assert_eq!((pdf, index), (1.0, 2));
+        // Empty steps are never chosen.
+        assert_eq!(distribution.sample(0.0).2, 1);
+        assert_eq!(distribution.pdf(0.9), 0.0);
+
+        let uniform = Distribution1D::new(vec![0.0, 0.0]);
+        assert_eq!(uniform.sample(0.75), (0.75, 1.0, 1));
+    }
+
+    #[test]
+    fn builds_orthonormal_bases() {
+        for &w in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 2.0, -3.0).normalize(),
+        ]
+        .iter()
+        {
+            let (u, v) = orthonormal_basis(&w);
+            assert!((u.length() - 1.0).abs() < 1e-12 && (v.length() - 1.0).abs() < 1e-12);
+            assert!(u.dot(&v).abs() < 1e-12 && u.dot(&w).abs() < 1e-12 && v.dot(&w).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn sample_density_matches_pdf() {
+        let function = [1.0, 0.0, 2.0, 5.0, 0.5, 0.0];
+        let distribution = Distribution2D::new(&function, 3, 2);
+        assert!((distribution.integral() - 8.5 / 6.0).abs() < 1e-12);
+
+        for &(u1, u2) in [(0.1, 0.2), (0.7, 0.9), (0.5, 0.5), (0.99, 0.01)].iter() {
+            let ((u, v), pdf) = distribution.sample(u1, u2);
+            assert!((pdf - distribution.pdf(u, v)).abs() < 1e-12);
+            let cell = (v * 2.0) as usize * 3 + (u * 3.0) as usize;
+            assert!((pdf - function[cell] / distribution.integral()).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::camera::Camera;
+use crate::environment::*;
+use crate::lights::Light;
+use crate::materials::*;
+use crate::objects::*;
+use crate::random::{random_f64, random_range};
+use crate::vec3::*;
+
+/// Parameters accepted by `Camera::new` and `Camera::with_shutter`, except
+/// for the aspect ratio which is derived from the size of the rendered image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraSettings {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vertical_fov: f64,
+    pub aperture: f64,
+    pub focus_distance: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl CameraSettings {
+    pub fn build(&self, aspect_ratio: f64) -> Camera {
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.vup,
+            self.vertical_fov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_distance,
+        )
+        .with_shutter(self.shutter_open, self.shutter_close)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: u32,
+    pub max_bounces: i32,
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+pub struct Scene {
+    pub camera: CameraSettings,
+    pub settings: RenderSettings,
+    pub environment: Box<dyn Environment>,
+    /// Every emissive object of `world`, so that they can be sampled.
+    pub lights: Vec<Box<dyn Light>>,
+    pub world: Box<dyn Hittable>,
+}
+
+impl Scene {
+    pub fn camera(&self) -> Camera {
+        self.camera
+            .build(self.settings.aspect_ratio())
+            .with_image_height(self.settings.height)
+    }
+}
+
+/// The scene from the cover of "Ray Tracing in One Weekend": a field of
+/// small random spheres around three big ones.
+pub fn random_spheres() -> Scene {
+    let ground_material = Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+
+    let mut objs = HittableCollection::new();
+    objs.add(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground_material,
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let x = (a as f64) + 0.9 * random_f64();
+            let y = (b as f64) + 0.9 * random_f64();
+            let choose_mat = random_f64();
+            let center = Point3::new(x, 0.2, y);
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.6 {
+                    // Diffuse
+                    let albedo = Color::random() * Color::random();
+                    objs.add(Box::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Lambertian::new(albedo)),
+                    }));
+                } else if choose_mat < 0.95 {
+                    // Metal
+                    let albedo = Color::random_range(0.5, 1.0);
+                    let fuzz = random_range(0.0, 0.5);
+                    objs.add(Box::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Metal::new(albedo, fuzz)),
+                    }));
+                } else {
+                    // Glass
+                    objs.add(Box::new(Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Box::new(Dielectric::new(1.5)),
+                    }));
+                }
+            }
+        }
+    }
+
+    objs.add(Box::new(Sphere {
+        center: Point3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Dielectric::new(1.5)),
+    }));
+
+    objs.add(Box::new(Sphere {
+        center: Point3::new(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
+    }));
+
+    objs.add(Box::new(Sphere {
+        center: Point3::new(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.01)),
+    }));
+
+    let aspect_ratio = 16.0 / 9.0;
+    let width = 1920;
+
+    Scene {
+        camera: CameraSettings {
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vertical_fov: 20.0,
+            aperture: 0.1,
+            focus_distance: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        },
+        settings: RenderSettings {
+            width,
+            height: (width as f64 / aspect_ratio) as usize,
+            samples_per_pixel: 500,
+            max_bounces: 50,
+        },
+        environment: Box::new(GradientEnvironment::default()),
+        lights: vec![],
+        world: Box::new(Bvh::from(objs)),
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use super::traits::{Texture, TexturePoint};
+use crate::vec3::Color;
+
+/// Alternates between two textures in a 3D grid of cubes with sides of
+/// length `scale`, so it does not depend on the texture coordinates.
+pub struct CheckerTexture {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl CheckerTexture {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f64) -> CheckerTexture {
+        CheckerTexture { even, odd, scale }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, point: &TexturePoint) -> Color {
+        let cell = |x: f64| (x / self.scale).floor() as i64;
+        let p = &point.p;
+        if (cell(p.x()) + cell(p.y()) + cell(p.z())) % 2 == 0 {
+            self.even.value(point)
+        } else {
+            self.odd.value(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::SolidColor;
+    use crate::vec3::Point3;
+
+    #[test]
+    fn alternates_between_cells() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let checker = CheckerTexture::new(
+            Arc::new(SolidColor::new(white)),
+            Arc::new(SolidColor::new(black)),
+            0.5,
+        );
+        let value = |x, y, z| checker.value(&TexturePoint::new(0.0, 0.0, Point3::new(x, y, z)));
+        assert_eq!(value(0.25, 0.25, 0.25), white);
+        assert_eq!(value(0.75, 0.25, 0.25), black);
+        assert_eq!(value(-0.25, 0.25, 0.25), black);
+        assert_eq!(value(-0.25, -0.25, 0.25), white);
+    }
+}
@@ -0,0 +1,179 @@
+use super::traits::{Texture, TexturePoint};
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// What an image texture shows outside of the [0, 1] range of texture
+/// coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+    /// Tiles the image.
+    Repeat,
+    /// Extends the pixels on the edges.
+    Clamp,
+    /// Tiles the image, flipping every other copy.
+    Mirror,
+}
+
+impl WrapMode {
+    /// Maps a pixel index, possibly outside of the image, to one inside.
+    fn apply(self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let index = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        index as usize
+    }
+}
+
+/// An image stretched over the texture coordinates, with (0, 0) at its
+/// bottom-left and (1, 1) at its top-right corner.
+///
+/// Lookups are filtered bilinearly, and blended between the levels of a
+/// MIP-map pyramid of ever smaller copies of the image when the footprint
+/// of the lookup covers more than a pixel, so that distant surfaces do not
+/// alias.
+pub struct ImageTexture {
+    /// The image followed by copies of half the size of the previous one,
+    /// down to a single pixel.
+    levels: Vec<Image>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    /// `image` must not be empty, which those returned by `load_image` never
+    /// are.
+    pub fn new(image: Image, wrap: WrapMode) -> ImageTexture {
+        let mut levels = vec![image];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = downsample(last, wrap);
+            levels.push(next);
+        }
+        ImageTexture { levels, wrap }
+    }
+
+    fn texel(&self, level: &Image, x: i64, y: i64) -> Color {
+        let x = self.wrap.apply(x, level.width);
+        let y = self.wrap.apply(y, level.height);
+        level.pixels[y * level.width + x]
+    }
+
+    /// Interpolates between the four pixels around (u, v) in `level`.
+    fn bilinear(&self, level: usize, u: f64, v: f64) -> Color {
+        let level = &self.levels[level];
+        let x = u * level.width as f64 - 0.5;
+        let y = (1.0 - v) * level.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        (1.0 - fy) * ((1.0 - fx) * self.texel(level, x0, y0) + fx * self.texel(level, x0 + 1, y0))
+            + fy * ((1.0 - fx) * self.texel(level, x0, y0 + 1)
+                + fx * self.texel(level, x0 + 1, y0 + 1))
+    }
+}
+
+/// Halves the size of `image`, averaging blocks of 2x2 pixels.
+fn downsample(image: &Image, wrap: WrapMode) -> Image {
+    let width = image.width.div_ceil(2);
+    let height = image.height.div_ceil(2);
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = Color::new(0.0, 0.0, 0.0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = wrap.apply((2 * x + dx) as i64, image.width);
+                let sy = wrap.apply((2 * y + dy) as i64, image.height);
+                sum += image.pixels[sy * image.width + sx];
+            }
+            pixels.push(sum / 4.0);
+        }
+    }
+    Image::new(width, height, pixels)
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, point: &TexturePoint) -> Color {
+        // The level whose pixels are as wide as the footprint.
+        let size = self.levels[0].width.max(self.levels[0].height) as f64;
+        let level = (point.footprint * size).max(1.0).log2();
+        let last = (self.levels.len() - 1) as f64;
+        if level >= last {
+            return self.bilinear(self.levels.len() - 1, point.u, point.v);
+        }
+        let lower = level.floor();
+        let t = level - lower;
+        let lower = lower as usize;
+        let color = self.bilinear(lower, point.u, point.v);
+        if t == 0.0 {
+            return color;
+        }
+        (1.0 - t) * color + t * self.bilinear(lower + 1, point.u, point.v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    fn value(texture: &ImageTexture, u: f64, v: f64, footprint: f64) -> f64 {
+        let mut point = TexturePoint::new(u, v, Point3::new(0.0, 0.0, 0.0));
+        point.footprint = footprint;
+        texture.value(&point).x()
+    }
+
+    #[test]
+    fn wraps_pixel_indices() {
+        assert_eq!(WrapMode::Repeat.apply(-1, 3), 2);
+        assert_eq!(WrapMode::Repeat.apply(4, 3), 1);
+        assert_eq!(WrapMode::Clamp.apply(-1, 3), 0);
+        assert_eq!(WrapMode::Clamp.apply(4, 3), 2);
+        assert_eq!(WrapMode::Mirror.apply(-1, 3), 0);
+        assert_eq!(WrapMode::Mirror.apply(4, 3), 1);
+        assert_eq!(WrapMode::Mirror.apply(6, 3), 0);
+    }
+
+    #[test]
+    fn filters_bilinearly() {
+        let pixels = (0..6).map(|i| Color::new(i as f64, 0.0, 0.0)).collect();
+        let texture = ImageTexture::new(Image::new(3, 2, pixels), WrapMode::Clamp);
+        // Pixel centres, top row first.
+        assert_eq!(value(&texture, 0.5 / 3.0, 0.75, 0.0), 0.0);
+        assert_eq!(value(&texture, 2.5 / 3.0, 0.25, 0.0), 5.0);
+        // Half way between the two rows and two columns.
+        assert!((value(&texture, 1.0 / 3.0, 0.5, 0.0) - 2.0).abs() < 1e-12);
+        // Clamped beyond the edge.
+        assert_eq!(value(&texture, -1.0, 0.75, 0.0), 0.0);
+    }
+
+    #[test]
+    fn averages_wide_footprints() {
+        // A fine checkerboard averages to grey from far away.
+        let pixels = (0..64)
+            .map(|i| {
+                let c = ((i % 8 + i / 8) % 2) as f64;
+                Color::new(c, c, c)
+            })
+            .collect();
+        let texture = ImageTexture::new(Image::new(8, 8, pixels), WrapMode::Repeat);
+        assert_eq!(texture.levels.len(), 4);
+        let sharp = value(&texture, 0.5 / 8.0, 1.0 - 0.5 / 8.0, 0.0);
+        assert_eq!(sharp, 0.0);
+        for &(u, v) in [(0.1, 0.2), (0.37, 0.81), (0.5, 0.5)].iter() {
+            assert!((value(&texture, u, v, 0.25) - 0.5).abs() < 1e-12);
+            assert!((value(&texture, u, v, 10.0) - 0.5).abs() < 1e-12);
+        }
+    }
+}
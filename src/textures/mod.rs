@@ -0,0 +1,11 @@
+pub mod checker;
+pub mod image;
+pub mod noise;
+pub mod solid;
+pub mod traits;
+
+pub use self::checker::*;
+pub use self::image::*;
+pub use self::noise::*;
+pub use self::solid::*;
+pub use self::traits::*;
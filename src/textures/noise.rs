@@ -0,0 +1,220 @@
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use super::traits::{Texture, TexturePoint};
+use crate::vec3::{Color, Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise: a smooth random function of space, varying on
+/// the scale of one unit, with values roughly in [-1, 1]. The same seed
+/// always gives the same function.
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    permutations: [Vec<usize>; 3],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| loop {
+                // Rejection sampling keeps the directions uniform.
+                let v = Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                );
+                let length_squared = v.length_squared();
+                if length_squared > 1e-6 && length_squared <= 1.0 {
+                    break v.normalize();
+                }
+            })
+            .collect();
+        let mut permutation = || {
+            let mut permutation: Vec<usize> = (0..POINT_COUNT).collect();
+            permutation.shuffle(&mut rng);
+            permutation
+        };
+        let permutations = [permutation(), permutation(), permutation()];
+        Perlin {
+            gradients,
+            permutations,
+        }
+    }
+
+    fn gradient(&self, x: i64, y: i64, z: i64) -> &Vec3 {
+        let mask = POINT_COUNT as i64 - 1;
+        let [px, py, pz] = &self.permutations;
+        &self.gradients[px[(x & mask) as usize] ^ py[(y & mask) as usize] ^ pz[(z & mask) as usize]]
+    }
+
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let (x, y, z) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - x, p.y() - y, p.z() - z);
+        let (x, y, z) = (x as i64, y as i64, z as i64);
+
+        // Hermite smoothing hides the grid the gradients are placed on.
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (su, sv, sw) = (smooth(u), smooth(v), smooth(w));
+
+        let mut sum = 0.0;
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    sum += (fi * su + (1.0 - fi) * (1.0 - su))
+                        * (fj * sv + (1.0 - fj) * (1.0 - sv))
+                        * (fk * sw + (1.0 - fk) * (1.0 - sw))
+                        * self.gradient(x + i, y + j, z + k).dot(&weight);
+                }
+            }
+        }
+        sum
+    }
+
+    /// Fractal sum of `octaves` layers of noise, each twice the frequency
+    /// and half the amplitude of the previous one.
+    pub fn fractal(&self, p: &Point3, octaves: u32) -> f64 {
+        self.octaves(p, octaves, |noise| noise)
+    }
+
+    /// Like `fractal`, but summing the absolute value of every layer, which
+    /// gives the creases of turbulent flow. Always positive.
+    pub fn turbulence(&self, p: &Point3, octaves: u32) -> f64 {
+        self.octaves(p, octaves, f64::abs)
+    }
+
+    fn octaves(&self, p: &Point3, octaves: u32, layer: impl Fn(f64) -> f64) -> f64 {
+        let mut sum = 0.0;
+        let mut p = *p;
+        let mut weight = 1.0;
+        for _ in 0..octaves.max(1) {
+            sum += weight * layer(self.noise(&p));
+            weight *= 0.5;
+            p = 2.0 * p;
+        }
+        sum
+    }
+}
+
+/// How a `NoiseTexture` turns noise into a value between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisePattern {
+    /// Fractal noise, smooth blotches.
+    Noise,
+    /// Turbulence, blotches with sharp creases.
+    Turbulence,
+    /// Veins across the x axis, distorted by turbulence.
+    Marble,
+    /// Rings around the y axis, distorted by turbulence.
+    Wood,
+}
+
+/// A solid texture blending between two colours following a pattern made
+/// of Perlin noise. It depends on the position in space only, so it shows
+/// no seams whatever the shape.
+pub struct NoiseTexture {
+    perlin: Perlin,
+    pattern: NoisePattern,
+    frequency: f64,
+    octaves: u32,
+    colors: [Color; 2],
+}
+
+impl NoiseTexture {
+    /// `frequency` scales space, so that the noise varies over lengths of
+    /// about `1 / frequency`; `colors` are shown for the values 0 and 1.
+    pub fn new(
+        pattern: NoisePattern,
+        seed: u64,
+        frequency: f64,
+        octaves: u32,
+        colors: [Color; 2],
+    ) -> NoiseTexture {
+        NoiseTexture {
+            perlin: Perlin::new(seed),
+            pattern,
+            frequency,
+            octaves,
+            colors,
+        }
+    }
+
+    fn pattern(&self, p: &Point3) -> f64 {
+        let p = self.frequency * *p;
+        let value = match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.perlin.fractal(&p, self.octaves)),
+            NoisePattern::Turbulence => self.perlin.turbulence(&p, self.octaves),
+            NoisePattern::Marble => {
+                // Veins about one unit apart, bent by turbulence.
+                let phase = 6.0 * p.x() + 5.0 * self.perlin.turbulence(&p, self.octaves);
+                0.5 * (1.0 + phase.sin())
+            }
+            NoisePattern::Wood => {
+                let radius = p.x().hypot(p.z()) + 0.5 * self.perlin.turbulence(&p, self.octaves);
+                // A triangle wave keeps the rings continuous.
+                1.0 - (2.0 * radius.rem_euclid(1.0) - 1.0).abs()
+            }
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, point: &TexturePoint) -> Color {
+        let t = self.pattern(&point.p);
+        (1.0 - t) * self.colors[0] + t * self.colors[1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_smooth_and_seeded() {
+        let perlin = Perlin::new(7);
+        let mut values = vec![];
+        for i in 0..200 {
+            let p = Point3::new(0.37 * i as f64, 0.11 * i as f64, -0.23 * i as f64);
+            let value = perlin.noise(&p);
+            assert!(value.abs() <= 1.0);
+            // Zero on the lattice, continuous everywhere.
+            assert!(perlin.noise(&Point3::new(i as f64, 3.0, -2.0)).abs() < 1e-12);
+            let nearby = perlin.noise(&(p + Vec3::new(1e-6, 1e-6, 1e-6)));
+            assert!((nearby - value).abs() < 1e-4);
+            values.push(value);
+        }
+        assert!(values.iter().any(|&value| value > 0.1));
+        assert!(values.iter().any(|&value| value < -0.1));
+
+        let p = Point3::new(1.5, 2.25, -0.75);
+        assert_eq!(Perlin::new(7).noise(&p), perlin.noise(&p));
+        assert_ne!(Perlin::new(8).noise(&p), perlin.noise(&p));
+    }
+
+    #[test]
+    fn patterns_stay_between_colors() {
+        let colors = [Color::new(0.0, 0.0, 0.0), Color::new(1.0, 0.5, 0.25)];
+        for &pattern in [
+            NoisePattern::Noise,
+            NoisePattern::Turbulence,
+            NoisePattern::Marble,
+            NoisePattern::Wood,
+        ]
+        .iter()
+        {
+            let texture = NoiseTexture::new(pattern, 1, 4.0, 5, colors);
+            for i in 0..100 {
+                let p = Point3::new(0.13 * i as f64, -0.07 * i as f64, 0.03 * i as f64);
+                let color = texture.value(&TexturePoint::new(0.0, 0.0, p));
+                let t = color.x();
+                assert!((0.0..=1.0).contains(&t), "{:?}: {}", pattern, t);
+                assert!((color.y() - 0.5 * t).abs() < 1e-12);
+            }
+        }
+    }
+}
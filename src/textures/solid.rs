@@ -0,0 +1,20 @@
+use super::traits::{Texture, TexturePoint};
+use crate::vec3::Color;
+
+/// The same colour everywhere.
+#[derive(Clone, Copy)]
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> SolidColor {
+        SolidColor { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _point: &TexturePoint) -> Color {
+        self.color
+    }
+}
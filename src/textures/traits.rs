@@ -0,0 +1,31 @@
+use crate::vec3::{Color, Point3};
+
+/// Where a texture is evaluated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TexturePoint {
+    /// Texture coordinates.
+    pub u: f64,
+    pub v: f64,
+    /// Position in space.
+    pub p: Point3,
+    /// Width of the area to average the texture over, in texture
+    /// coordinates. Zero for a single point.
+    pub footprint: f64,
+}
+
+impl TexturePoint {
+    pub fn new(u: f64, v: f64, p: Point3) -> TexturePoint {
+        TexturePoint {
+            u,
+            v,
+            p,
+            footprint: 0.0,
+        }
+    }
+}
+
+/// A colour that varies over a surface, looked up from the texture
+/// coordinates of a hit or its position in space.
+pub trait Texture: Sync + Send {
+    fn value(&self, point: &TexturePoint) -> Color;
+}
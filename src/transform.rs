@@ -0,0 +1,232 @@
+use std::ops::Mul;
+
+use crate::objects::Aabb;
+use crate::vec3::*;
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+
+    pub fn identity() -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Matrix4 { m }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Matrix4 { m }
+    }
+
+    /// Determinant of the upper left 3x3 block, the linear part of an
+    /// affine transform.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+/// Affine transform kept along with its inverse, so that rays can be
+/// taken into the space of an object and normals brought back out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    pub fn translation(offset: &Vec3) -> Transform {
+        let mut matrix = Matrix4::identity();
+        let mut inverse = Matrix4::identity();
+        for axis in 0..3 {
+            matrix.m[axis][3] = offset[axis];
+            inverse.m[axis][3] = -offset[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Scales each axis by the matching component of `factors`, none of
+    /// which may be zero.
+    pub fn scaling(factors: &Vec3) -> Transform {
+        let mut matrix = Matrix4::identity();
+        let mut inverse = Matrix4::identity();
+        for axis in 0..3 {
+            matrix.m[axis][axis] = factors[axis];
+            inverse.m[axis][axis] = 1.0 / factors[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Counter-clockwise rotation by `degrees` around `axis`, looking down
+    /// the axis towards the origin.
+    pub fn rotation(axis: &Vec3, degrees: f64) -> Transform {
+        let a = axis.normalize();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut matrix = Matrix4::identity();
+        for i in 0..3 {
+            for j in 0..3 {
+                let delta = if i == j { 1.0 } else { 0.0 };
+                matrix.m[i][j] = cos * delta + (1.0 - cos) * a[i] * a[j];
+            }
+        }
+        matrix.m[0][1] -= sin * a.z();
+        matrix.m[0][2] += sin * a.y();
+        matrix.m[1][0] += sin * a.z();
+        matrix.m[1][2] -= sin * a.x();
+        matrix.m[2][0] -= sin * a.y();
+        matrix.m[2][1] += sin * a.x();
+        // Rotations are orthogonal.
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    /// The transform applying `self` first and `next` afterwards.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    /// Factor by which the transform scales volumes.
+    pub fn determinant(&self) -> f64 {
+        self.matrix.determinant3()
+    }
+
+    pub fn point(&self, p: &Point3) -> Point3 {
+        let m = &self.matrix.m;
+        Point3::new(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.matrix.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    /// Transforms a surface normal, which takes the inverse transpose to
+    /// stay perpendicular to the transformed surface. The result is not
+    /// normalized.
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        let m = &self.inverse.m;
+        Vec3::new(
+            m[0][0] * n.x() + m[1][0] * n.y() + m[2][0] * n.z(),
+            m[0][1] * n.x() + m[1][1] * n.y() + m[2][1] * n.z(),
+            m[0][2] * n.x() + m[1][2] * n.y() + m[2][2] * n.z(),
+        )
+    }
+
+    /// Box enclosing the transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let corner = |i: usize| {
+            let mut corner = bbox.min;
+            for axis in 0..3 {
+                if i & (1 << axis) != 0 {
+                    corner[axis] = bbox.max[axis];
+                }
+            }
+            corner
+        };
+        let first = self.point(&corner(0));
+        (1..8).fold(Aabb::new(first, first), |bbox, i| {
+            bbox.include(&self.point(&corner(i)))
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn composes_with_inverse() {
+        let transform = Transform::scaling(&Vec3::new(2.0, 1.0, 0.5))
+            .then(&Transform::rotation(&Vec3::new(0.0, 1.0, 0.0), 90.0))
+            .then(&Transform::translation(&Vec3::new(1.0, 2.0, 3.0)));
+        let p = Point3::new(1.0, 1.0, 1.0);
+        assert_close(transform.point(&p), Point3::new(1.5, 3.0, 1.0));
+        assert_close(transform.inverse().point(&transform.point(&p)), p);
+        assert!((transform.determinant() - 1.0).abs() < 1e-12);
+
+        // Normals stay perpendicular to transformed tangents.
+        let tangent = Vec3::new(1.0, -1.0, 0.0);
+        let normal = Vec3::new(1.0, 1.0, 0.0);
+        let dot = transform.vector(&tangent).dot(&transform.normal(&normal));
+        assert!(dot.abs() < 1e-12);
+    }
+
+    #[test]
+    fn bounds_rotated_boxes() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let rotated = Transform::rotation(&Vec3::new(0.0, 0.0, 1.0), 45.0).bounding_box(&bbox);
+        let s = 2f64.sqrt();
+        assert_close(rotated.min, Point3::new(-s, -s, -1.0));
+        assert_close(rotated.max, Point3::new(s, s, 1.0));
+    }
+}
@@ -3,14 +3,14 @@ use std::ops::AddAssign;
 use std::ops::Div;
 use std::ops::DivAssign;
 use std::ops::Index;
+use std::ops::IndexMut;
 use std::ops::Mul;
 use std::ops::MulAssign;
 use std::ops::Neg;
 use std::ops::Sub;
 use std::ops::SubAssign;
-use std::usize;
 
-use rand::Rng;
+use crate::random::{random_f64, random_range};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vec3 {
@@ -26,19 +26,17 @@ impl Vec3 {
     }
 
     pub fn random() -> Vec3 {
-        let mut rng = rand::thread_rng();
         Vec3 {
-            e: [rng.gen(), rng.gen(), rng.gen()],
+            e: [random_f64(), random_f64(), random_f64()],
         }
     }
 
     pub fn random_range(min: f64, max: f64) -> Vec3 {
-        let mut rng = rand::thread_rng();
         Vec3 {
             e: [
-                rng.gen_range(min..max),
-                rng.gen_range(min..max),
-                rng.gen_range(min..max),
+                random_range(min, max),
+                random_range(min, max),
+                random_range(min, max),
             ],
         }
     }
@@ -54,9 +52,8 @@ impl Vec3 {
     }
 
     pub fn random_in_unit_disk() -> Vec3 {
-        let mut rng = rand::thread_rng();
         loop {
-            let p = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
+            let p = Vec3::new(random_range(-1.0, 1.0), random_range(-1.0, 1.0), 0.0);
             if p.length_squared() >= 1.0 {
                 continue;
             }
@@ -89,7 +86,7 @@ impl Vec3 {
     }
 
     pub fn normalize(&self) -> Vec3 {
-        self.clone() / self.length()
+        *self / self.length()
     }
 
     pub fn dot(&self, other: &Vec3) -> f64 {
@@ -104,8 +101,35 @@ impl Vec3 {
         )
     }
 
+    pub fn min(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.x().min(other.x()),
+            self.y().min(other.y()),
+            self.z().min(other.z()),
+        )
+    }
+
+    pub fn max(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.x().max(other.x()),
+            self.y().max(other.y()),
+            self.z().max(other.z()),
+        )
+    }
+
+    /// Whether all components are so close to zero that the vector has no
+    /// usable direction.
+    pub fn near_zero(&self) -> bool {
+        const EPSILON: f64 = 1e-8;
+        self.e.iter().all(|c| c.abs() < EPSILON)
+    }
+
+    pub fn abs(&self) -> Vec3 {
+        Vec3::new(self.x().abs(), self.y().abs(), self.z().abs())
+    }
+
     pub fn reflect(&self, normal: &Vec3) -> Vec3 {
-        return *self - 2.0 * self.dot(normal) * (*normal);
+        *self - 2.0 * self.dot(normal) * (*normal)
     }
 
     pub fn refract(&self, normal: &Vec3, ref_ratio: f64) -> Vec3 {
@@ -218,6 +242,12 @@ impl Index<usize> for Vec3 {
     }
 }
 
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, i: usize) -> &mut f64 {
+        &mut self.e[i]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,6 +320,6 @@ mod tests {
     fn cross_product() {
         let x = Vec3::new(2.0, 2.0, 2.0);
         let y = Vec3::new(-3.0, 3.0, -1.0);
-        assert_eq!(x.cross(&y), Vec3::new(-8.0, -4.0, 8.0));
+        assert_eq!(x.cross(&y), Vec3::new(-8.0, -4.0, 12.0));
     }
 }